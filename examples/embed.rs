@@ -0,0 +1,73 @@
+//! Embeds the task list in a custom layout, next to a pane of its own.
+//!
+//! Run with `cargo run --example embed`, move with j/k and quit with q.
+
+use std::{error::Error, io};
+
+use crossterm::{
+    event::{self, Event, KeyCode},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use todo_tui::{
+    task::Task,
+    ui::widget::{NumberMode, RowFormat, TaskListState, TaskListWidget},
+};
+use tui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    widgets::{Block, Borders, Paragraph},
+    Terminal,
+};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut tasks = vec![
+        Task::new(String::from("Water the plants"), None),
+        Task::new(String::from("Review pull requests"), None),
+        Task::new(String::from("Book train tickets"), None),
+    ];
+    tasks[0].done = true;
+    let mut state = TaskListState::default();
+    state.select(Some(0));
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+
+    loop {
+        terminal.draw(|f| {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+                .split(f.size());
+            let list = TaskListWidget::new(&tasks)
+                .format(RowFormat {
+                    numbers: NumberMode::Absolute,
+                    ..RowFormat::default()
+                })
+                .block(Block::default().borders(Borders::ALL).title("Todo"));
+            f.render_stateful_widget(list, chunks[0], &mut state);
+
+            let selected = state
+                .selected()
+                .map_or("nothing", |i| tasks[i].msg.as_str());
+            let dashboard = Paragraph::new(format!("Selected: {}", selected))
+                .block(Block::default().borders(Borders::ALL).title("Dashboard"));
+            f.render_widget(dashboard, chunks[1]);
+        })?;
+
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Char('q') => break,
+                KeyCode::Char('j') => state.next(tasks.len()),
+                KeyCode::Char('k') => state.previous(tasks.len()),
+                _ => {}
+            }
+        }
+    }
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    Ok(())
+}
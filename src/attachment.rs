@@ -0,0 +1,79 @@
+//! Local files referenced by tasks.
+//!
+//! Paths are stored exactly as entered, so a `~/notes.md` attachment keeps
+//! working on every machine with the same layout. They are only expanded
+//! when checked or opened.
+
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+};
+
+/// Expands a leading `~` to the home directory. Any other path, relative
+/// ones included, is returned unchanged.
+///
+/// ```
+/// use std::{env, path::Path};
+/// use todo_tui::attachment::expand_tilde;
+///
+/// env::set_var("HOME", "/home/me");
+/// assert_eq!(expand_tilde(Path::new("~/notes.md")), Path::new("/home/me/notes.md"));
+/// assert_eq!(expand_tilde(Path::new("~")), Path::new("/home/me"));
+/// assert_eq!(expand_tilde(Path::new("docs/a.md")), Path::new("docs/a.md"));
+/// assert_eq!(expand_tilde(Path::new("~other/a.md")), Path::new("~other/a.md"));
+/// ```
+pub fn expand_tilde(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => match env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")) {
+            Some(home) => PathBuf::from(home).join(rest),
+            None => path.to_path_buf(),
+        },
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Whether the attachment currently points at something on disk. Relative
+/// paths are resolved against the working directory.
+///
+/// ```
+/// use std::path::Path;
+/// use todo_tui::attachment::exists;
+///
+/// assert!(exists(Path::new("Cargo.toml")));
+/// assert!(!exists(Path::new("no/such/file.pdf")));
+/// assert!(!exists(Path::new("~/no/such/file.pdf")));
+/// ```
+pub fn exists(path: &Path) -> bool {
+    expand_tilde(path).exists()
+}
+
+/// Opens the attachment with the platform's default application.
+pub fn open(path: &Path) -> io::Result<()> {
+    let mut child = opener()
+        .arg(expand_tilde(path))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn opener() -> Command {
+    Command::new("open")
+}
+
+#[cfg(windows)]
+fn opener() -> Command {
+    let mut command = Command::new("cmd");
+    command.args(["/C", "start", ""]);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn opener() -> Command {
+    Command::new("xdg-open")
+}
@@ -0,0 +1,109 @@
+//! Commands changing or printing a task file without opening the UI.
+//!
+//! Apart from `export`, they work on the first list of the file and number
+//! its tasks from 1, a task's subtasks following it, as they are shown in
+//! the UI. None of them reads a file written by an older version, which is
+//! only upgraded from the UI, as it backs the file up first.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{
+    export,
+    storage::{self, TaskList, DEFAULT_LIST},
+    task::{self, Task},
+};
+
+/// Appends a task to the first list of the file at `path`.
+pub fn add(path: &Path, msg: String, details: Option<String>) -> io::Result<()> {
+    let mut lists = load(path)?;
+    if lists.is_empty() {
+        lists.push(TaskList {
+            name: String::from(DEFAULT_LIST),
+            tasks: Vec::new(),
+            archive: Vec::new(),
+        });
+    }
+    lists[0].tasks.push(Task::new(msg, details));
+    storage::save(path, &lists)
+}
+
+/// Writes the tasks of the first list of the file at `path` to `out`, one
+/// per line, e.g. `2 [x] Water plants`.
+pub fn list(path: &Path, out: &mut impl Write) -> io::Result<()> {
+    let lists = load(path)?;
+    let rows = task::flatten(
+        lists
+            .into_iter()
+            .next()
+            .map_or_else(Vec::new, |list| list.tasks),
+    );
+    let width = rows.len().to_string().len();
+    for (i, row) in rows.iter().enumerate() {
+        writeln!(
+            out,
+            "{:>width$} [{}] {}{}",
+            i + 1,
+            if row.done { "x" } else { " " },
+            if row.subtask { "  " } else { "" },
+            row.msg,
+            width = width
+        )?;
+    }
+    Ok(())
+}
+
+/// Marks the task numbered `number` in the first list of the file at
+/// `path` done, or pending if it is done. As in the UI, a locked task is
+/// left alone and a task is only done once its subtasks are, see
+/// [`task::set_done`].
+pub fn done(path: &Path, number: usize) -> io::Result<()> {
+    let mut lists = load(path)?;
+    let Some(list) = lists.first_mut() else {
+        return Err(invalid(format!("there is no task {}", number)));
+    };
+    let mut rows = task::flatten(std::mem::take(&mut list.tasks));
+    let Some(i) = number.checked_sub(1).filter(|&i| i < rows.len()) else {
+        return Err(invalid(format!("there is no task {}", number)));
+    };
+    if rows[i].locked {
+        return Err(invalid(format!("\"{}\" is locked", rows[i].msg)));
+    }
+    let done = !rows[i].done;
+    if !task::set_done(&mut rows, i, done) {
+        return Err(invalid(if done {
+            format!("finish the subtasks of \"{}\" first", rows[i].msg)
+        } else {
+            let parent = &rows[task::family(&rows, i).start];
+            format!("\"{}\" is locked", parent.msg)
+        }));
+    }
+    list.tasks = task::nest(rows);
+    storage::save(path, &lists)
+}
+
+/// Writes every list of the file at `path` to `out` as Markdown
+/// checklists, see [`export::lists`].
+pub fn export(path: &Path, out: &Path) -> io::Result<()> {
+    fs::write(out, export::lists(&load(path)?))
+}
+
+/// Lists of the file at `path`. A file written by an older version is
+/// refused, so that it is only upgraded from the UI, which backs it up.
+fn load(path: &Path) -> io::Result<Vec<TaskList>> {
+    let loaded = storage::load(path)?;
+    if loaded.legacy {
+        return Err(invalid(format!(
+            "{} was written by an older version, open it in todo-tui to upgrade it",
+            path.display()
+        )));
+    }
+    Ok(loaded.lists)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
@@ -0,0 +1,23 @@
+//! Due dates as typed by the user.
+
+use chrono::{Duration, NaiveDate};
+
+/// Reads a due date relative to `today`: `YYYY-MM-DD`, `today` or
+/// `tomorrow`, ignoring case and surrounding spaces.
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use todo_tui::due::parse;
+///
+/// let today = NaiveDate::from_ymd_opt(2024, 2, 28).unwrap();
+/// assert_eq!(parse("2024-03-01", today), NaiveDate::from_ymd_opt(2024, 3, 1));
+/// assert_eq!(parse(" Tomorrow ", today), NaiveDate::from_ymd_opt(2024, 2, 29));
+/// assert_eq!(parse("2024-02-30", today), None);
+/// ```
+pub fn parse(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match input.trim().to_lowercase().as_str() {
+        "today" => Some(today),
+        "tomorrow" => Some(today + Duration::days(1)),
+        date => NaiveDate::parse_from_str(date, "%Y-%m-%d").ok(),
+    }
+}
@@ -0,0 +1,97 @@
+//! Writing tasks out as a GitHub-flavored Markdown checklist.
+
+use std::path::{Path, PathBuf};
+
+use crate::{storage::TaskList, task::Task};
+
+/// Where the checklist of the task file at `path` goes unless told
+/// otherwise: `todo.md` next to it.
+pub fn default_path(path: &Path) -> PathBuf {
+    path.with_file_name("todo.md")
+}
+
+/// Every list as a heading followed by its checklist, see [`markdown`].
+///
+/// ```
+/// use todo_tui::{export::lists, storage::TaskList, task::Task};
+///
+/// let work = TaskList {
+///     name: String::from("Work"),
+///     tasks: vec![Task::new(String::from("Ship"), None)],
+///     archive: Vec::new(),
+/// };
+/// let home = TaskList {
+///     name: String::from("Home"),
+///     tasks: Vec::new(),
+///     archive: Vec::new(),
+/// };
+/// assert_eq!(lists(&[work, home]), "# Work\n\n- [ ] Ship\n\n# Home\n");
+/// ```
+pub fn lists(lists: &[TaskList]) -> String {
+    let mut out = String::new();
+    for list in lists {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("# {}\n", escape(&list.name)));
+        if !list.tasks.is_empty() {
+            out.push('\n');
+            write_tasks(&mut out, &list.tasks, "");
+        }
+    }
+    out
+}
+
+/// The tasks as a checklist, subtasks nested under their task and details
+/// quoted below the task they belong to.
+///
+/// ```
+/// use todo_tui::{export::markdown, task::Task};
+///
+/// let mut task = Task::new(String::from("Fix [docs] *now*"), Some(String::from("a\nb")));
+/// task.children.push(Task::new(String::from("Proofread"), None));
+/// task.children[0].done = true;
+/// assert_eq!(
+///     markdown(&[task]),
+///     "- [ ] Fix \\[docs\\] \\*now\\*\n  > a\n  > b\n  - [x] Proofread\n"
+/// );
+/// ```
+pub fn markdown(tasks: &[Task]) -> String {
+    let mut out = String::new();
+    write_tasks(&mut out, tasks, "");
+    out
+}
+
+fn write_tasks(out: &mut String, tasks: &[Task], indent: &str) {
+    for task in tasks {
+        let mark = if task.done { "x" } else { " " };
+        out.push_str(&format!("{}- [{}] {}\n", indent, mark, escape(&task.msg)));
+        let nested = format!("{}  ", indent);
+        if let Some(details) = &task.details {
+            for line in details.lines() {
+                match line {
+                    "" => out.push_str(&format!("{}>\n", nested)),
+                    line => out.push_str(&format!("{}> {}\n", nested, escape(line))),
+                }
+            }
+        }
+        write_tasks(out, &task.children, &nested);
+    }
+}
+
+/// `text` on one line with the characters Markdown would read as markup
+/// escaped.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -1,11 +1,93 @@
+pub mod attachment;
+pub mod cli;
+pub mod due;
+pub mod export;
+pub mod storage;
 pub mod ui;
 
 pub mod task {
-    #[derive(Debug, Clone)]
+    use std::{ops::Range, path::PathBuf};
+
+    use chrono::{DateTime, Local, NaiveDate};
+    use serde::{Deserialize, Serialize};
+    use serde_json::{Map, Value};
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct Task {
         pub done: bool,
         pub msg: String,
+        #[serde(default)]
         pub details: Option<String>,
+        /// Files the task refers to, as entered by the user.
+        #[serde(default)]
+        pub attachments: Vec<PathBuf>,
+        #[serde(default)]
+        pub due: Option<NaiveDate>,
+        /// Protects the task from being edited, toggled or deleted.
+        #[serde(default)]
+        pub locked: bool,
+        #[serde(default)]
+        pub priority: Priority,
+        /// When the task was added, unknown for tasks saved before it was
+        /// recorded.
+        #[serde(default)]
+        pub created_at: Option<DateTime<Local>>,
+        /// When the task was last marked done, kept by [`Task::set_done`].
+        #[serde(default)]
+        pub completed_at: Option<DateTime<Local>>,
+        /// Steps of the task, as saved. A flattened list holds them as the
+        /// rows following their parent instead, see [`flatten`].
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub children: Vec<Task>,
+        /// Set on the rows of a flattened list that are subtasks of the task
+        /// above them. Never saved.
+        #[serde(skip)]
+        pub subtask: bool,
+        /// Fields other tools sharing the file added to the task, saved back
+        /// as they were read.
+        ///
+        /// ```
+        /// use todo_tui::task::Task;
+        ///
+        /// let json = r#"{"done":false,"msg":"Water plants","x-energy":"low","x-where":{"rooms":["hall"]}}"#;
+        /// let task: Task = serde_json::from_str(json).unwrap();
+        /// assert_eq!(task.extra["x-energy"], "low");
+        /// let saved = serde_json::to_value(&task).unwrap();
+        /// assert_eq!(saved["x-where"]["rooms"][0], "hall");
+        /// ```
+        #[serde(flatten)]
+        pub extra: Map<String, Value>,
+    }
+
+    #[derive(
+        Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+    )]
+    pub enum Priority {
+        Low,
+        #[default]
+        Normal,
+        High,
+        Urgent,
+    }
+
+    impl Priority {
+        /// Next level up, staying at `Urgent`.
+        pub fn raise(self) -> Self {
+            match self {
+                Priority::Low => Priority::Normal,
+                Priority::Normal => Priority::High,
+                Priority::High | Priority::Urgent => Priority::Urgent,
+            }
+        }
+
+        /// Next level down, staying at `Low`.
+        pub fn lower(self) -> Self {
+            match self {
+                Priority::Low | Priority::Normal => Priority::Low,
+                Priority::High => Priority::Normal,
+                Priority::Urgent => Priority::High,
+            }
+        }
     }
 
     impl Task {
@@ -14,7 +96,122 @@ pub mod task {
                 done: false,
                 msg,
                 details,
+                attachments: Vec::new(),
+                due: None,
+                locked: false,
+                priority: Priority::Normal,
+                created_at: Some(Local::now()),
+                completed_at: None,
+                children: Vec::new(),
+                subtask: false,
+                extra: Map::new(),
+            }
+        }
+
+        /// Marks the task done or pending, stamping when it was completed.
+        /// Marking a done task done again keeps its first stamp.
+        pub fn set_done(&mut self, done: bool) {
+            if done != self.done {
+                self.completed_at = done.then(Local::now);
             }
+            self.done = done;
+        }
+    }
+
+    /// Lays the tasks out as rows, each task followed by its children marked
+    /// as subtasks. Only one level is flattened: grandchildren stay inside
+    /// their parent row.
+    ///
+    /// ```
+    /// use todo_tui::task::{flatten, nest, Task};
+    ///
+    /// let mut release = Task::new(String::from("Release v2"), None);
+    /// release.children.push(Task::new(String::from("Tag"), None));
+    /// let rows = flatten(vec![release, Task::new(String::from("Rest"), None)]);
+    /// let titles: Vec<_> = rows.iter().map(|row| (row.msg.as_str(), row.subtask)).collect();
+    /// assert_eq!(titles, [("Release v2", false), ("Tag", true), ("Rest", false)]);
+    /// assert_eq!(nest(rows)[0].children[0].msg, "Tag");
+    /// ```
+    pub fn flatten(tasks: Vec<Task>) -> Vec<Task> {
+        let mut rows = Vec::with_capacity(tasks.len());
+        for mut task in tasks {
+            let children = std::mem::take(&mut task.children);
+            task.subtask = false;
+            rows.push(task);
+            rows.extend(children.into_iter().map(|mut child| {
+                child.subtask = true;
+                child
+            }));
+        }
+        rows
+    }
+
+    /// Regroups rows laid out by [`flatten`] into tasks holding their
+    /// children. A subtask row with no task above it becomes a task.
+    pub fn nest(rows: Vec<Task>) -> Vec<Task> {
+        let mut tasks: Vec<Task> = Vec::new();
+        for mut row in rows {
+            let subtask = std::mem::take(&mut row.subtask);
+            match tasks.last_mut() {
+                Some(parent) if subtask => parent.children.push(row),
+                _ => tasks.push(row),
+            }
+        }
+        tasks
+    }
+
+    /// Whether the task owning row `i` of a flattened list can be given a
+    /// pending subtask. A task is never done while one of its subtasks is
+    /// pending, so a done task is reopened first, which a locked one cannot
+    /// be.
+    pub fn can_reopen(rows: &[Task], i: usize) -> bool {
+        let parent = &rows[family(rows, i).start];
+        !parent.done || !parent.locked
+    }
+
+    /// Marks row `i` of a flattened list done or pending, keeping a task from
+    /// being done while one of its subtasks is pending: a task is only marked
+    /// done once its subtasks are, and a subtask marked pending reopens its
+    /// task, see [`can_reopen`]. Returns whether the change was allowed.
+    ///
+    /// ```
+    /// use todo_tui::task::{flatten, set_done, Task};
+    ///
+    /// let mut release = Task::new(String::from("Release v2"), None);
+    /// release.children.push(Task::new(String::from("Tag"), None));
+    /// let mut rows = flatten(vec![release]);
+    /// assert!(!set_done(&mut rows, 0, true));
+    /// assert!(set_done(&mut rows, 1, true) && set_done(&mut rows, 0, true));
+    /// assert!(set_done(&mut rows, 1, false));
+    /// assert!(!rows[0].done);
+    /// ```
+    pub fn set_done(rows: &mut [Task], i: usize, done: bool) -> bool {
+        let family = family(rows, i);
+        let allowed = if done {
+            rows[i].subtask || rows[family.start + 1..family.end].iter().all(|t| t.done)
+        } else {
+            !rows[i].subtask || can_reopen(rows, i)
+        };
+        if allowed {
+            rows[i].set_done(done);
+            if !done {
+                rows[family.start].set_done(false);
+            }
+        }
+        allowed
+    }
+
+    /// Rows of the task owning row `i` of a flattened list: the task itself,
+    /// or the parent of the subtask at `i`, followed by its subtasks.
+    pub fn family(rows: &[Task], i: usize) -> Range<usize> {
+        let mut start = i;
+        while start > 0 && rows[start].subtask {
+            start -= 1;
+        }
+        let mut end = start + 1;
+        while end < rows.len() && rows[end].subtask {
+            end += 1;
         }
+        start..end
     }
 }
@@ -1,5 +1,99 @@
-use todo_tui::ui::start_ui;
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use todo_tui::{
+    cli, export, storage,
+    ui::{start_ui, Options},
+};
+
+const USAGE: &str = "usage: todo-tui [--no-migrate] [--low-power] [--brackets] [--wrap-held] [FILE]
+       todo-tui [FILE] add TITLE [--details DETAILS]
+       todo-tui [FILE] list
+       todo-tui [FILE] done NUMBER
+       todo-tui [FILE] export [OUTPUT]";
 
 fn main() {
-    start_ui().unwrap();
+    let mut options = Options::default();
+    let mut args = env::args().skip(1);
+    let mut command = None;
+    for arg in args.by_ref() {
+        match arg.as_str() {
+            "--no-migrate" => options.no_migrate = true,
+            "--low-power" => options.low_power = true,
+            "--brackets" => options.brackets = true,
+            "--wrap-held" => options.wrap_held = true,
+            "add" | "list" | "done" | "export" => {
+                command = Some(arg);
+                break;
+            }
+            path if !path.starts_with('-') && options.path.is_none() => {
+                options.path = Some(PathBuf::from(path));
+            }
+            _ => usage_error(&format!("unknown argument {}", arg)),
+        }
+    }
+    let result = match command {
+        None => start_ui(options),
+        Some(command) => {
+            let path = options.path.unwrap_or_else(storage::default_path);
+            run(&command, &path, args.collect()).map_err(Into::into)
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("todo-tui: {}", e);
+        process::exit(1);
+    }
+}
+
+/// Runs a subcommand on the task file at `path`.
+fn run(command: &str, path: &Path, args: Vec<String>) -> io::Result<()> {
+    let mut args = args.into_iter();
+    match command {
+        "add" => {
+            let Some(msg) = args.next() else {
+                usage_error("add needs a title");
+            };
+            let details = match (args.next().as_deref(), args.next()) {
+                (None, _) => None,
+                (Some("--details"), Some(details)) => Some(details),
+                _ => usage_error("add takes a title and --details only"),
+            };
+            refuse_extra(args);
+            cli::add(path, msg, details)
+        }
+        "list" => {
+            refuse_extra(args);
+            cli::list(path, &mut io::stdout().lock())
+        }
+        "export" => {
+            let out = args
+                .next()
+                .map_or_else(|| export::default_path(path), PathBuf::from);
+            refuse_extra(args);
+            cli::export(path, &out)
+        }
+        "done" => {
+            let Some(number) = args.next().and_then(|n| n.parse().ok()) else {
+                usage_error("done needs a task number");
+            };
+            refuse_extra(args);
+            cli::done(path, number)
+        }
+        _ => usage_error(&format!("unknown command {}", command)),
+    }
+}
+
+/// Refuses arguments left over by a subcommand.
+fn refuse_extra(mut args: impl Iterator<Item = String>) {
+    if let Some(arg) = args.next() {
+        usage_error(&format!("unknown argument {}", arg));
+    }
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("todo-tui: {}\n{}", message, USAGE);
+    process::exit(2);
 }
@@ -0,0 +1,238 @@
+//! Saving and loading the task lists as JSON.
+
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::task::Task;
+
+/// File the tasks are kept in: `todo-tui/tasks.json` in the XDG data
+/// directory, `~/.local/share` unless `XDG_DATA_HOME` says otherwise.
+pub fn default_path() -> PathBuf {
+    let data_dir = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+    match data_dir {
+        Some(dir) => dir.join("todo-tui").join("tasks.json"),
+        None => PathBuf::from("tasks.json"),
+    }
+}
+
+/// A named list as saved, with the tasks archived from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskList {
+    pub name: String,
+    #[serde(default)]
+    pub tasks: Vec<Task>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub archive: Vec<Task>,
+}
+
+/// Name of the list read from a file holding a single list.
+pub const DEFAULT_LIST: &str = "Tasks";
+
+/// Lists read from a task file.
+#[derive(Debug, Clone)]
+pub struct Loaded {
+    pub lists: Vec<TaskList>,
+    /// Whether the file holds a bare array of tasks, as older versions
+    /// wrote, to be rewritten with [`upgrade`].
+    pub legacy: bool,
+}
+
+/// What the task file holds, `L` being the lists read or written.
+#[derive(Serialize, Deserialize)]
+struct Document<L> {
+    lists: L,
+}
+
+/// File older versions kept the archived tasks in, next to the task file
+/// at `path`: `tasks.json` went with `tasks.archive.json`.
+fn archive_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or(path.as_os_str());
+    let mut name = stem.to_owned();
+    name.push(".archive.json");
+    path.with_file_name(name)
+}
+
+/// Reads the lists saved at `path`. A missing or blank file has no lists; a
+/// file that does not parse is an `InvalidData` error naming it.
+///
+/// A file holding a bare array of tasks, as older versions wrote, is read
+/// as a single list named [`DEFAULT_LIST`], along with its archive file.
+pub fn load(path: &Path) -> io::Result<Loaded> {
+    let Some(content) = read(path)? else {
+        return Ok(Loaded {
+            lists: Vec::new(),
+            legacy: false,
+        });
+    };
+    if !content.trim_start().starts_with('[') {
+        let document: Document<Vec<TaskList>> = parse(path, &content)?;
+        return Ok(Loaded {
+            lists: document.lists,
+            legacy: false,
+        });
+    }
+    let tasks = parse(path, &content)?;
+    let archive_path = archive_path(path);
+    let archive = match read(&archive_path)? {
+        Some(content) => parse(&archive_path, &content)?,
+        None => Vec::new(),
+    };
+    Ok(Loaded {
+        lists: vec![TaskList {
+            name: String::from(DEFAULT_LIST),
+            tasks,
+            archive,
+        }],
+        legacy: true,
+    })
+}
+
+/// Rewrites the task file of an older version at `path` as `lists`, read
+/// from it. The old files are backed up first, see [`backup`], and the
+/// archive file is removed once its tasks are saved. Returns where the
+/// task file was copied.
+pub fn upgrade(path: &Path, lists: &[TaskList]) -> io::Result<PathBuf> {
+    let copy = backup(path)?;
+    save(path, lists)?;
+    match fs::remove_file(archive_path(path)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(copy),
+    }
+}
+
+/// Copies the task file at `path` and the archive file of older versions,
+/// if there is one, next to them with a `.pre-upgrade` suffix. Returns
+/// where the task file was copied.
+pub fn backup(path: &Path) -> io::Result<PathBuf> {
+    let copy = |from: &Path| {
+        let mut to = from.as_os_str().to_owned();
+        to.push(".pre-upgrade");
+        let to = PathBuf::from(to);
+        fs::copy(from, &to).map(|_| to)
+    };
+    let archive = archive_path(path);
+    if archive.exists() {
+        copy(&archive)?;
+    }
+    copy(path)
+}
+
+/// Content of the file at `path`, `None` if it is missing or blank.
+fn read(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) if content.trim().is_empty() => Ok(None),
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(io::Error::new(
+            e.kind(),
+            format!("cannot read {}: {}", path.display(), e),
+        )),
+    }
+}
+
+fn parse<T: DeserializeOwned>(path: &Path, content: &str) -> io::Result<T> {
+    serde_json::from_str(content).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not a valid task file: {}", path.display(), e),
+        )
+    })
+}
+
+/// Writes `lists` to `path`, creating its directory if needed. Each list's
+/// tasks are written in the order given, which is its manual order.
+///
+/// The lists go to a temporary file next to `path` that is then renamed
+/// over it, so a crash mid-save leaves the previous lists intact.
+pub fn save(path: &Path, lists: &[TaskList]) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut file = File::create(&tmp)?;
+    serde_json::to_writer_pretty(&mut file, &Document { lists })?;
+    file.write_all(b"\n")?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory for `test` to write files in.
+    fn scratch_dir(test: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("todo-tui-storage-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    const LEGACY: &str = r#"[{"done": false, "msg": "Buy milk"}, {"done": true, "msg": "Call"}]"#;
+    const LEGACY_ARCHIVE: &str = r#"[{"done": true, "msg": "Old"}]"#;
+
+    #[test]
+    fn detects_a_bare_array_as_legacy() {
+        let dir = scratch_dir("detect");
+        let path = dir.join("tasks.json");
+        fs::write(&path, format!("  \n{}", LEGACY)).unwrap();
+        fs::write(dir.join("tasks.archive.json"), LEGACY_ARCHIVE).unwrap();
+        let loaded = load(&path).unwrap();
+        assert!(loaded.legacy);
+        assert_eq!(loaded.lists.len(), 1);
+        assert_eq!(loaded.lists[0].name, DEFAULT_LIST);
+        assert_eq!(loaded.lists[0].tasks.len(), 2);
+        assert_eq!(loaded.lists[0].archive[0].msg, "Old");
+
+        save(&path, &loaded.lists).unwrap();
+        assert!(!load(&path).unwrap().legacy);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn saving_keeps_an_archive_file_alone() {
+        let dir = scratch_dir("keep");
+        let path = dir.join("tasks.json");
+        let archive = dir.join("tasks.archive.json");
+        fs::write(&archive, LEGACY_ARCHIVE).unwrap();
+        save(&path, &[]).unwrap();
+        assert_eq!(fs::read_to_string(&archive).unwrap(), LEGACY_ARCHIVE);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn upgrade_backs_up_then_rewrites() {
+        let dir = scratch_dir("upgrade");
+        let path = dir.join("tasks.json");
+        let archive = dir.join("tasks.archive.json");
+        fs::write(&path, LEGACY).unwrap();
+        fs::write(&archive, LEGACY_ARCHIVE).unwrap();
+        let lists = load(&path).unwrap().lists;
+
+        let copy = upgrade(&path, &lists).unwrap();
+        assert_eq!(copy, dir.join("tasks.json.pre-upgrade"));
+        assert_eq!(fs::read_to_string(&copy).unwrap(), LEGACY);
+        assert_eq!(
+            fs::read_to_string(dir.join("tasks.archive.json.pre-upgrade")).unwrap(),
+            LEGACY_ARCHIVE
+        );
+        assert!(!archive.exists());
+        let loaded = load(&path).unwrap();
+        assert!(!loaded.legacy);
+        assert_eq!(loaded.lists[0].tasks.len(), 2);
+        assert_eq!(loaded.lists[0].archive[0].msg, "Old");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, RecvError, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crossterm::event::{self, Event, KeyEvent};
+
+/// Everything the event loop reacts to, whoever produced it.
+#[derive(Debug)]
+pub enum AppEvent {
+    Input(KeyEvent),
+    Resize,
+    Tick,
+    /// The terminal could not be read anymore.
+    InputFailed(io::Error),
+}
+
+/// Handle given to background work so it can post events to the loop.
+#[derive(Clone)]
+pub struct Worker {
+    tx: Sender<AppEvent>,
+}
+
+impl Worker {
+    /// Posts `event`, returning `false` once the event loop has stopped.
+    pub fn post(&self, event: AppEvent) -> bool {
+        self.tx.send(event).is_ok()
+    }
+
+    /// Runs `job` on its own thread with a handle to post its results.
+    pub fn spawn<F>(&self, job: F)
+    where
+        F: FnOnce(Worker) + Send + 'static,
+    {
+        let worker = self.clone();
+        thread::spawn(move || job(worker));
+    }
+}
+
+/// The single channel all events are delivered through.
+pub struct Events {
+    rx: Receiver<AppEvent>,
+    worker: Worker,
+    /// Event read ahead while collapsing repeats, delivered next.
+    peeked: Option<AppEvent>,
+    /// Milliseconds between two ticks, read by the ticking thread.
+    tick_rate: Arc<AtomicU64>,
+}
+
+impl Events {
+    /// Creates the channel, fed by the terminal and a tick every `tick_rate`.
+    pub fn new(tick_rate: Duration) -> Self {
+        let events = Events::channel();
+        events.set_tick_rate(tick_rate);
+        events.worker.spawn(read_terminal);
+        let rate = Arc::clone(&events.tick_rate);
+        events.worker.spawn(move |worker| loop {
+            thread::sleep(Duration::from_millis(rate.load(Ordering::Relaxed)));
+            if !worker.post(AppEvent::Tick) {
+                break;
+            }
+        });
+        events
+    }
+
+    /// The channel alone, without the terminal reader and the ticks.
+    pub(super) fn channel() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Events {
+            rx,
+            worker: Worker { tx },
+            peeked: None,
+            tick_rate: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Handle to post events to the loop, standing in for the terminal.
+    #[cfg(test)]
+    pub(super) fn worker(&self) -> Worker {
+        self.worker.clone()
+    }
+
+    /// Changes the interval between ticks, from the tick after next on.
+    pub fn set_tick_rate(&self, tick_rate: Duration) {
+        let millis = u64::try_from(tick_rate.as_millis()).unwrap_or(u64::MAX);
+        self.tick_rate.store(millis, Ordering::Relaxed);
+    }
+
+    /// Blocks until the next event arrives.
+    pub fn next(&mut self) -> Result<AppEvent, RecvError> {
+        match self.peeked.take() {
+            Some(event) => Ok(event),
+            None => self.rx.recv(),
+        }
+    }
+
+    /// Drains the presses of `key` already queued right behind the one being
+    /// handled and returns how many there were. The first other event is
+    /// kept for the next call to `next`, so ordering is preserved.
+    pub fn take_repeats(&mut self, key: KeyEvent) -> usize {
+        let mut count = 0;
+        while self.peeked.is_none() {
+            match self.rx.try_recv() {
+                Ok(AppEvent::Input(next)) if next == key => count += 1,
+                Ok(other) => self.peeked = Some(other),
+                Err(_) => break,
+            }
+        }
+        count
+    }
+}
+
+fn read_terminal(worker: Worker) {
+    loop {
+        let event = match event::read() {
+            Ok(Event::Key(key)) => AppEvent::Input(key),
+            Ok(Event::Resize(_, _)) => AppEvent::Resize,
+            Ok(_) => continue,
+            Err(e) => {
+                worker.post(AppEvent::InputFailed(e));
+                break;
+            }
+        };
+        if !worker.post(event) {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    use super::*;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn take_repeats_coalesces_a_burst_and_keeps_what_follows() {
+        let mut events = Events::channel();
+        let worker = events.worker();
+        for event in [key('j'), key('j'), key('j'), key('k'), key('j')] {
+            assert!(worker.post(AppEvent::Input(event)));
+        }
+        assert!(matches!(events.next(), Ok(AppEvent::Input(k)) if k == key('j')));
+        assert_eq!(events.take_repeats(key('j')), 2);
+        assert!(matches!(events.next(), Ok(AppEvent::Input(k)) if k == key('k')));
+        assert_eq!(events.take_repeats(key('k')), 0);
+        assert!(matches!(events.next(), Ok(AppEvent::Input(k)) if k == key('j')));
+        assert_eq!(events.take_repeats(key('j')), 0);
+    }
+
+    #[test]
+    fn delivers_events_posted_by_workers_in_order() {
+        let mut events = Events::channel();
+        let worker = events.worker();
+        assert!(worker.post(AppEvent::Resize));
+        worker.spawn(|worker| {
+            worker.post(AppEvent::Input(key('a')));
+            worker.post(AppEvent::Tick);
+        });
+        assert!(matches!(events.next(), Ok(AppEvent::Resize)));
+        assert!(matches!(events.next(), Ok(AppEvent::Input(k)) if k == key('a')));
+        assert!(matches!(events.next(), Ok(AppEvent::Tick)));
+    }
+
+    #[test]
+    fn post_fails_once_the_loop_is_gone() {
+        let events = Events::channel();
+        let worker = events.worker();
+        drop(events);
+        assert!(!worker.post(AppEvent::Tick));
+    }
+
+    #[test]
+    fn set_tick_rate_is_stored_in_milliseconds() {
+        let events = Events::channel();
+        events.set_tick_rate(Duration::from_secs(5));
+        assert_eq!(events.tick_rate.load(Ordering::Relaxed), 5_000);
+    }
+}
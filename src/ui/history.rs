@@ -0,0 +1,293 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Local};
+
+use super::StateFullList;
+use crate::task::Task;
+
+/// Number of actions that can be undone.
+pub const LIMIT: usize = 50;
+
+/// Bytes of task text the undoable actions may hold together, so a few
+/// edits of huge details cannot pile up. The latest action is always kept.
+const MAX_BYTES: usize = 4 * 1024 * 1024;
+
+/// A change made to the task list, holding what is needed to revert it.
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// A task was inserted at this index.
+    Added(usize),
+    /// This task was removed from this index.
+    Deleted(usize, Task),
+    /// The task at this index was marked done or pending; this is its
+    /// previous completion time.
+    Toggled(usize, Option<DateTime<Local>>),
+    /// The task at this index was changed; this is its previous state.
+    Edited(usize, Task),
+    /// The tasks at these indices were swapped.
+    Moved(usize, usize),
+    /// The `n` rows from `from` were moved further in the list, to start at
+    /// `to`, as `(from, n, to)`.
+    MovedRows(usize, usize, usize),
+    /// The list was rearranged, the task now at `k` coming from `order[k]`.
+    Reordered(Vec<usize>),
+    /// The list was changed as a whole; these are its previous tasks.
+    Replaced(Vec<Task>),
+    /// The `n` rows at `i` were moved into the archive, at `a`, as
+    /// `(i, a, n)`.
+    Archived(usize, usize, usize),
+    /// The `n` archived rows at `a` were moved back into the list, at `i`, as
+    /// `(a, i, n)`.
+    Restored(usize, usize, usize),
+    /// A task was inserted into the archive at this index.
+    ArchiveAdded(usize),
+    /// This task was removed from the archive at this index.
+    ArchiveDeleted(usize, Task),
+}
+
+impl Action {
+    /// Approximate bytes of task text held by the action.
+    fn size(&self) -> usize {
+        match self {
+            Action::Deleted(_, task)
+            | Action::Edited(_, task)
+            | Action::ArchiveDeleted(_, task) => task_size(task),
+            Action::Reordered(order) => order.len() * std::mem::size_of::<usize>(),
+            Action::Replaced(tasks) => tasks.iter().map(task_size).sum(),
+            _ => 0,
+        }
+    }
+
+    /// Reverts the action on `list` and `archive`, selecting the task it
+    /// concerns, and returns the action reverting that, or `None` if the
+    /// lists no longer match.
+    fn revert(
+        self,
+        list: &mut StateFullList<Task>,
+        archive: &mut StateFullList<Task>,
+    ) -> Option<Action> {
+        let len = list.items.len();
+        let archived = archive.items.len();
+        match self {
+            Action::Added(i) if i < len => Some(Action::Deleted(i, list.remove(i))),
+            Action::Deleted(i, task) => {
+                let i = i.min(len);
+                list.insert(i, task);
+                Some(Action::Added(i))
+            }
+            Action::Toggled(i, completed_at) if i < len => {
+                let task = &mut list.items[i];
+                task.done = !task.done;
+                let completed_at = std::mem::replace(&mut task.completed_at, completed_at);
+                list.state.select(Some(i));
+                Some(Action::Toggled(i, completed_at))
+            }
+            Action::Edited(i, task) if i < len => {
+                let current = std::mem::replace(&mut list.items[i], task);
+                list.state.select(Some(i));
+                Some(Action::Edited(i, current))
+            }
+            Action::Moved(i, j) if i < len && j < len => {
+                list.items.swap(i, j);
+                list.state.select(Some(i));
+                Some(Action::Moved(i, j))
+            }
+            Action::MovedRows(from, n, to) if to + n <= len => {
+                let rows = list.remove_rows(to..to + n);
+                list.insert_rows(from.min(len - n), rows);
+                Some(Action::MovedRows(to, n, from))
+            }
+            Action::Reordered(order) if order.len() == len => {
+                let mut inverse = vec![0; len];
+                for (k, &i) in order.iter().enumerate() {
+                    inverse[i] = k;
+                }
+                list.permute(&inverse);
+                Some(Action::Reordered(inverse))
+            }
+            Action::Replaced(tasks) => {
+                let current = std::mem::replace(&mut list.items, tasks);
+                let selected = list.state.selected();
+                list.state.select(match list.items.len() {
+                    0 => None,
+                    len => selected.map(|s| s.min(len - 1)),
+                });
+                Some(Action::Replaced(current))
+            }
+            Action::Archived(i, a, n) if a + n <= archived => {
+                let i = i.min(len);
+                list.insert_rows(i, archive.remove_rows(a..a + n));
+                Some(Action::Restored(a, i, n))
+            }
+            Action::Restored(a, i, n) if i + n <= len => {
+                let a = a.min(archived);
+                archive.insert_rows(a, list.remove_rows(i..i + n));
+                Some(Action::Archived(i, a, n))
+            }
+            Action::ArchiveAdded(a) if a < archived => {
+                Some(Action::ArchiveDeleted(a, archive.remove(a)))
+            }
+            Action::ArchiveDeleted(a, task) => {
+                let a = a.min(archived);
+                archive.insert(a, task);
+                Some(Action::ArchiveAdded(a))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Why old actions were dropped from the undo history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eviction {
+    /// More than [`LIMIT`] actions were recorded.
+    Count,
+    /// The actions held more than [`MAX_BYTES`] of task text.
+    Size,
+}
+
+/// Bytes of text in `task` and its children.
+fn task_size(task: &Task) -> usize {
+    task.msg.len()
+        + task.details.as_ref().map_or(0, String::len)
+        + task
+            .attachments
+            .iter()
+            .map(|path| path.as_os_str().len())
+            .sum::<usize>()
+        + task.children.iter().map(task_size).sum::<usize>()
+}
+
+/// Undo and redo stacks of the actions applied to the task list.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: VecDeque<Action>,
+    redo: Vec<Action>,
+    /// Sum of the sizes of the actions in `undo`.
+    undo_bytes: usize,
+    /// Whether an action has ever been dropped to stay within the limits.
+    evicted: bool,
+}
+
+impl History {
+    /// Remembers a freshly applied action, dropping whatever could be redone
+    /// and the oldest actions past the last [`LIMIT`] or [`MAX_BYTES`].
+    /// Returns why, the first time an old action is dropped.
+    pub fn record(&mut self, action: Action) -> Option<Eviction> {
+        let first = !self.evicted;
+        let eviction = self.push_undo(action);
+        self.redo.clear();
+        eviction.filter(|_| first)
+    }
+
+    /// Pushes `action` on the undo stack, returning why older actions were
+    /// dropped, if any were.
+    fn push_undo(&mut self, action: Action) -> Option<Eviction> {
+        self.undo_bytes += action.size();
+        self.undo.push_back(action);
+        let mut eviction = None;
+        while self.undo.len() > LIMIT || self.undo.len() > 1 && self.undo_bytes > MAX_BYTES {
+            let reason = if self.undo.len() > LIMIT {
+                Eviction::Count
+            } else {
+                Eviction::Size
+            };
+            if let Some(oldest) = self.undo.pop_front() {
+                self.undo_bytes -= oldest.size();
+                self.evicted = true;
+                eviction = eviction.or(Some(reason));
+            }
+        }
+        eviction
+    }
+
+    fn pop_undo(&mut self) -> Option<Action> {
+        let action = self.undo.pop_back()?;
+        self.undo_bytes -= action.size();
+        Some(action)
+    }
+
+    /// Reverts the most recent action, returning `false` if there is none.
+    pub fn undo(
+        &mut self,
+        list: &mut StateFullList<Task>,
+        archive: &mut StateFullList<Task>,
+    ) -> bool {
+        match self
+            .pop_undo()
+            .and_then(|action| action.revert(list, archive))
+        {
+            Some(inverse) => {
+                self.redo.push(inverse);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Applies again the most recently undone action, returning `false` if
+    /// there is none.
+    pub fn redo(
+        &mut self,
+        list: &mut StateFullList<Task>,
+        archive: &mut StateFullList<Task>,
+    ) -> bool {
+        match self
+            .redo
+            .pop()
+            .and_then(|action| action.revert(list, archive))
+        {
+            Some(inverse) => {
+                self.push_undo(inverse);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A deleted task holding `bytes` bytes of details.
+    fn deleted(bytes: usize) -> Action {
+        Action::Deleted(0, Task::new(String::new(), Some("x".repeat(bytes))))
+    }
+
+    #[test]
+    fn record_keeps_the_last_limit_actions() {
+        let mut history = History::default();
+        for i in 0..LIMIT {
+            assert_eq!(history.record(Action::Added(i)), None);
+        }
+        assert_eq!(history.undo.len(), LIMIT);
+        assert_eq!(history.record(Action::Added(LIMIT)), Some(Eviction::Count));
+        assert_eq!(history.undo.len(), LIMIT);
+        assert!(matches!(history.undo.front(), Some(Action::Added(1))));
+        // Only the first eviction is reported.
+        assert_eq!(history.record(Action::Added(LIMIT + 1)), None);
+        assert!(matches!(history.undo.front(), Some(Action::Added(2))));
+    }
+
+    #[test]
+    fn record_keeps_the_actions_within_max_bytes() {
+        let mut history = History::default();
+        assert_eq!(history.record(deleted(MAX_BYTES / 2)), None);
+        assert_eq!(history.record(deleted(MAX_BYTES / 2)), None);
+        assert_eq!(history.undo_bytes, MAX_BYTES);
+        assert_eq!(history.record(deleted(1)), Some(Eviction::Size));
+        assert_eq!(history.undo.len(), 2);
+        assert_eq!(history.undo_bytes, MAX_BYTES / 2 + 1);
+    }
+
+    #[test]
+    fn record_always_keeps_the_latest_action() {
+        let mut history = History::default();
+        assert_eq!(history.record(deleted(MAX_BYTES + 1)), None);
+        assert_eq!(history.undo.len(), 1);
+        assert_eq!(history.record(Action::Added(0)), Some(Eviction::Size));
+        assert_eq!(history.undo.len(), 1);
+        assert_eq!(history.undo_bytes, 0);
+    }
+}
@@ -0,0 +1,3412 @@
+mod event;
+mod history;
+mod plan;
+mod power;
+mod random;
+mod sanitize;
+mod title;
+mod view;
+pub mod widget;
+
+use std::{
+    error::Error,
+    fs,
+    io::{self, Write},
+    ops::Range,
+    panic,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    event::*,
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use tui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans, Text},
+    widgets::{
+        Block, BorderType, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Wrap,
+    },
+    Frame, Terminal,
+};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use chrono::{Local, NaiveDate};
+
+use crate::{
+    attachment, due, export, storage,
+    task::{self, Priority, Task},
+};
+use event::{AppEvent, Events};
+use history::{Action, Eviction, History};
+use plan::{Op, Plan};
+use power::Power;
+use random::Rng;
+use sanitize::{sanitize, sanitize_line};
+use widget::{Checkbox, NumberMode, RowFormat, TaskListState, TaskListWidget};
+
+/// How long a prefix key waits for the key completing its sequence.
+const SEQUENCE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Presses of the same navigation key closer than this count as the key
+/// being held down.
+const HELD_WINDOW: Duration = Duration::from_millis(100);
+
+/// How long a randomly picked task stays flashed.
+const FLASH_DURATION: Duration = Duration::from_millis(600);
+
+/// Shortest time between two updates of the terminal window title.
+const TITLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Longest task title accepted by the input, in characters.
+const MAX_TITLE_LEN: usize = 256;
+
+/// Bindings available while browsing the list, listed in full by the help
+/// overlay. The bottom bar shows as many as fit, in this order.
+const NORMAL_HINTS: &[(&str, &str)] = &[
+    ("?", "help"),
+    ("q", "Quit"),
+    ("n", "New task"),
+    ("o", "subtask"),
+    ("d", "delete"),
+    ("j", "down"),
+    ("k", "up"),
+    ("gg", "top"),
+    ("G", "bottom"),
+    ("/", "filter"),
+    ("c", "hide done"),
+    ("a", "archive"),
+    ("A", "archive view"),
+    ("Tab/S-Tab", "next/previous list"),
+    ("N", "new list"),
+    ("R", "rename list"),
+    ("J/K", "move task"),
+    ("L", "lock"),
+    ("+/-", "priority"),
+    ("1/2/3", "high/normal/low"),
+    ("p", "priority order"),
+    ("s", "done last"),
+    ("B", "bulk reorder"),
+    ("D", "clear done"),
+    ("Z", "low power"),
+    ("T", "toggle all"),
+    ("u", "undo"),
+    ("C-r", "redo"),
+    ("C-e", "export markdown"),
+    ("Enter", "Mark done"),
+    ("e", "edit"),
+    ("E", "edit details"),
+    ("l", "scroll details"),
+    ("i", "edit inline"),
+    ("I", "insert position"),
+    ("#", "numbers"),
+    ("@", "attachments"),
+    ("r", "random"),
+];
+
+/// Two-key bindings: prefix key, completing key, action and description.
+///
+/// When a prefix also has a single-key binding, the sequence wins and the
+/// single binding fires once the sequence times out.
+const SEQUENCES: &[(char, char, SequenceAction, &str)] =
+    &[('g', 'g', SequenceAction::SelectFirst, "top")];
+
+/// Key hints shown in the bottom bar while a popup or the inline editor
+/// captures input.
+const EDITING_HINTS: &[(&str, &str)] = &[("Enter", "confirm"), ("Esc", "cancel")];
+
+/// Bindings available while browsing the archive, shown like
+/// [`NORMAL_HINTS`].
+const ARCHIVE_HINTS: &[(&str, &str)] = &[
+    ("?", "help"),
+    ("q", "Quit"),
+    ("A", "tasks"),
+    ("Tab/S-Tab", "next/previous list"),
+    ("j", "down"),
+    ("k", "up"),
+    ("gg", "top"),
+    ("G", "bottom"),
+    ("/", "filter"),
+    ("Enter", "restore"),
+    ("d", "delete"),
+    ("u", "undo"),
+    ("C-r", "redo"),
+    ("#", "numbers"),
+    ("Z", "low power"),
+    ("l", "scroll details"),
+];
+
+/// Key hints shown in the bottom bar while the help overlay is open.
+const HELP_HINTS: &[(&str, &str)] = &[("j/k", "scroll"), ("?/q/Esc", "close")];
+
+/// Key hints shown in the bottom bar while the details pane is focused.
+const DETAILS_HINTS: &[(&str, &str)] = &[("j/k", "scroll"), ("h/Esc", "back to list")];
+
+/// Key hints shown in the bottom bar while the filter query is typed.
+const SEARCH_HINTS: &[(&str, &str)] = &[("Enter", "keep filter"), ("Esc", "clear filter")];
+
+/// Key hints shown in the bottom bar while a change awaits confirmation.
+const CONFIRM_HINTS: &[(&str, &str)] = &[("y/Enter", "confirm"), ("n/Esc", "cancel")];
+
+/// Key hints shown in the bottom bar while an old task file awaits its
+/// upgrade.
+const UPGRADE_HINTS: &[(&str, &str)] = &[("y/Enter", "upgrade"), ("n/Esc", "quit")];
+
+/// Key hints shown in the bottom bar while a bulk reorder is planned.
+const REORDER_HINTS: &[(&str, &str)] = &[
+    ("j/k", "move"),
+    ("J/K", "move task"),
+    ("x", "done"),
+    ("d", "delete"),
+    ("Enter", "apply"),
+    ("Esc", "discard"),
+];
+
+/// Key hints shown in the bottom bar while the attachments popup is open.
+const ATTACHMENT_HINTS: &[(&str, &str)] = &[
+    ("a", "add"),
+    ("d", "remove"),
+    ("o", "open"),
+    ("j/k", "move"),
+    ("Esc", "close"),
+];
+
+enum InputMode {
+    Normal,
+    Editing,
+    /// Editing the selected task's title in place, without a popup.
+    Inline,
+    /// Typing the query filtering the list.
+    Search,
+}
+
+#[derive(Clone, Copy)]
+enum SequenceAction {
+    SelectFirst,
+}
+
+/// Where a newly created task is placed in the list.
+#[derive(Clone, Copy)]
+enum InsertPosition {
+    BelowSelection,
+    Top,
+    End,
+}
+
+impl InsertPosition {
+    /// Index at which a new task lands among `rows`, below the subtasks of
+    /// the selected task.
+    fn index(self, rows: &[Task], selected: Option<usize>) -> usize {
+        match self {
+            InsertPosition::BelowSelection => selected
+                .filter(|&i| i < rows.len())
+                .map_or(rows.len(), |i| task::family(rows, i).end),
+            InsertPosition::Top => 0,
+            InsertPosition::End => rows.len(),
+        }
+    }
+
+    fn cycle(self) -> Self {
+        match self {
+            InsertPosition::BelowSelection => InsertPosition::Top,
+            InsertPosition::Top => InsertPosition::End,
+            InsertPosition::End => InsertPosition::BelowSelection,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            InsertPosition::BelowSelection => "below",
+            InsertPosition::Top => "top",
+            InsertPosition::End => "end",
+        }
+    }
+}
+
+/// List shown in the main panel.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum View {
+    Tasks,
+    /// Done tasks put away with `a`.
+    Archive,
+}
+
+/// Pane j/k act on.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    List,
+    /// The details pane, which j/k scroll.
+    Details,
+}
+
+/// Order the list is shown in. Only `Manual` is stored: the other modes
+/// sort a copy of the indices and never rearrange the tasks themselves.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortMode {
+    /// The order the tasks were added and moved in.
+    Manual,
+    /// Urgent first, ties keeping their manual order.
+    Priority,
+}
+
+impl SortMode {
+    fn toggle(self) -> Self {
+        match self {
+            SortMode::Manual => SortMode::Priority,
+            SortMode::Priority => SortMode::Manual,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Popup {
+    NewTaskName,
+    NewTaskDetails,
+    /// Optional due date, last step of creating a task.
+    NewTaskDue,
+    /// Title of the task at this index being rewritten.
+    EditTaskName(usize),
+    /// Details of the task at this index being rewritten.
+    EditTaskDetails(usize),
+    /// Attachments of the selected task.
+    Attachments,
+    /// Path of an attachment being added to the selected task.
+    AttachmentPath,
+    /// Asks before the selected task is deleted.
+    ConfirmDelete,
+    /// Asks before the selected task is locked or unlocked.
+    ConfirmLock,
+    /// Every binding of the current view.
+    Help,
+    /// Name of a list being created.
+    NewList,
+    /// New name of the shown list.
+    RenameList,
+    /// Asks before a task file written by an older version is upgraded.
+    Upgrade,
+    /// Bulk changes to the list, applied together, see [`App::plan`].
+    Reorder,
+    /// File the lists are exported to as Markdown.
+    ExportPath,
+}
+
+/// How the app is started, as set on the command line.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Task file to use instead of [`storage::default_path`].
+    pub path: Option<PathBuf>,
+    /// Opens a task file written by an older version read-only instead of
+    /// upgrading it.
+    pub no_migrate: bool,
+    /// Starts in low power mode, see [`Power`].
+    pub low_power: bool,
+    /// Marks tasks with `[x]`/`[ ]` instead of `✓`.
+    pub brackets: bool,
+    /// Lets a held navigation key wrap around the ends of the list.
+    pub wrap_held: bool,
+}
+
+struct StateFullList<T> {
+    state: TaskListState,
+    items: Vec<T>,
+}
+
+impl<T> StateFullList<T> {
+    /// Inserts `item` at `index` and moves the selection onto it.
+    fn insert(&mut self, index: usize, item: T) {
+        self.items.insert(index, item);
+        self.state.select(Some(index));
+    }
+
+    /// Swaps the items at `i` and `j`, the selection following its item.
+    fn swap(&mut self, i: usize, j: usize) {
+        self.items.swap(i, j);
+        match self.state.selected() {
+            Some(s) if s == i => self.state.select(Some(j)),
+            Some(s) if s == j => self.state.select(Some(i)),
+            _ => {}
+        }
+    }
+
+    /// Removes the item at `index` and selects the one now there, or the
+    /// new last item when the tail was removed. The selection is only
+    /// cleared once the list is empty.
+    fn remove(&mut self, index: usize) -> T {
+        let item = self.items.remove(index);
+        let selected = match self.items.len() {
+            0 => None,
+            len => Some(index.min(len - 1)),
+        };
+        self.state.select(selected);
+        item
+    }
+
+    /// Rearranges the items so the one at `order[k]` ends up at `k`, the
+    /// selection following its item. `order` must be a permutation of the
+    /// item indices.
+    fn permute(&mut self, order: &[usize]) {
+        let mut old: Vec<Option<T>> = self.items.drain(..).map(Some).collect();
+        self.items = order.iter().filter_map(|&i| old[i].take()).collect();
+        if let Some(s) = self.state.selected() {
+            self.state.select(order.iter().position(|&i| i == s));
+        }
+    }
+
+    /// Removes the items in `range` and selects the one now at its start, as
+    /// [`StateFullList::remove`] does.
+    fn remove_rows(&mut self, range: Range<usize>) -> Vec<T> {
+        let start = range.start;
+        let items: Vec<T> = self.items.drain(range).collect();
+        let selected = match self.items.len() {
+            0 => None,
+            len => Some(start.min(len - 1)),
+        };
+        self.state.select(selected);
+        items
+    }
+
+    /// Inserts `items` at `index` and selects the first of them.
+    fn insert_rows(&mut self, index: usize, items: Vec<T>) {
+        self.items.splice(index..index, items);
+        self.state.select(Some(index));
+    }
+
+    /// Removes the selected item, the selection staying at the same index
+    /// as with [`StateFullList::remove`].
+    fn remove_selected(&mut self) -> Option<T> {
+        let i = self.state.selected().filter(|&i| i < self.items.len())?;
+        Some(self.remove(i))
+    }
+}
+
+/// A named task list with its own archive, selections and undo history.
+struct Project {
+    name: String,
+    list: StateFullList<Task>,
+    /// Archived tasks, most recently archived first.
+    archive: StateFullList<Task>,
+    history: History,
+}
+
+impl Project {
+    fn new(name: String) -> Self {
+        Project::load(storage::TaskList {
+            name,
+            tasks: Vec::new(),
+            archive: Vec::new(),
+        })
+    }
+
+    /// Project holding a list as read from the task file.
+    fn load(saved: storage::TaskList) -> Self {
+        Project {
+            name: saved.name,
+            list: StateFullList {
+                state: TaskListState::default(),
+                items: task::flatten(saved.tasks),
+            },
+            archive: StateFullList {
+                state: TaskListState::default(),
+                items: task::flatten(saved.archive),
+            },
+            history: History::default(),
+        }
+    }
+
+    /// The list as written to the task file.
+    fn saved(&self) -> storage::TaskList {
+        storage::TaskList {
+            name: self.name.clone(),
+            tasks: task::nest(self.list.items.clone()),
+            archive: task::nest(self.archive.items.clone()),
+        }
+    }
+}
+
+impl StateFullList<Task> {
+    /// Removes the unlocked done tasks, keeping a task while any of its
+    /// subtasks stays. The selection moves to the nearest remaining task,
+    /// looking down first. Returns how many tasks were removed and how many
+    /// done ones were kept for being locked.
+    fn clear_done(&mut self) -> (usize, usize) {
+        let removable = |task: &Task| task.done && !task.locked;
+        let mut keep = vec![true; self.items.len()];
+        let mut start = 0;
+        while start < self.items.len() {
+            let family = task::family(&self.items, start);
+            for (keep, task) in keep[family.clone()]
+                .iter_mut()
+                .zip(&self.items[family.clone()])
+                .skip(1)
+            {
+                *keep = !removable(task);
+            }
+            keep[start] = !removable(&self.items[start])
+                || keep[family.start + 1..family.end].contains(&true);
+            start = family.end;
+        }
+
+        let selected = self.state.selected().and_then(|s| {
+            let kept = |i: &usize| keep[*i];
+            (s..keep.len())
+                .find(kept)
+                .or_else(|| (0..s).rev().find(kept))
+        });
+        let mut i = 0;
+        let before = self.items.len();
+        self.items.retain(|_| {
+            i += 1;
+            keep[i - 1]
+        });
+        let selected = selected.map(|s| keep[..s].iter().filter(|&&k| k).count());
+        self.state.select(selected);
+        let locked = self
+            .items
+            .iter()
+            .filter(|task| task.done && task.locked)
+            .count();
+        (before - self.items.len(), locked)
+    }
+
+    /// Marks every unlocked task done, or pending when they all already
+    /// are. A task held by a locked one through [`task::set_done`] stays as
+    /// it is too. Returns whether the tasks were marked done, how many
+    /// changed and how many locked ones were left as they are.
+    fn toggle_all(&mut self) -> (bool, usize, usize) {
+        let done = self.items.iter().any(|task| !task.done && !task.locked);
+        let (mut changed, mut locked) = (0, 0);
+        // Subtasks come after their task, so going backwards marks them done
+        // before it.
+        for i in (0..self.items.len()).rev() {
+            if self.items[i].done == done {
+                continue;
+            }
+            if self.items[i].locked {
+                locked += 1;
+            } else if task::set_done(&mut self.items, i, done) {
+                changed += 1;
+            }
+        }
+        (done, changed, locked)
+    }
+}
+
+struct App {
+    pub popup: Option<Popup>,
+    pub input_mode: InputMode,
+    pub input: Vec<String>,
+    /// Why the last confirmed input was refused, until it is changed.
+    pub input_error: Option<String>,
+    /// Byte offset of the cursor in the input being typed, at its end when
+    /// `None`.
+    pub cursor: Option<usize>,
+    pub insert_position: InsertPosition,
+    pub numbers: NumberMode,
+    pub checkbox: Checkbox,
+    /// Prefix key waiting for the rest of its sequence, and when it was hit.
+    pub pending: Option<(char, Instant)>,
+    /// Direction and time of the last move, to tell held keys from presses.
+    pub last_nav: Option<(bool, Instant)>,
+    /// Whether holding a navigation key wraps around the ends of the list.
+    pub wrap_when_held: bool,
+    /// Selected row of the attachments popup.
+    pub attachment: usize,
+    /// Changes planned in the reorder popup.
+    pub plan: Option<Plan>,
+    pub power: Power,
+    /// Message replacing the key hints until the next key press.
+    pub status: Option<String>,
+    pub rng: Rng,
+    /// Task chosen by the last random pick, not picked again right away.
+    pub last_pick: Option<usize>,
+    /// When the selected task started flashing.
+    pub flash: Option<Instant>,
+    /// Named task lists, at least one.
+    pub projects: Vec<Project>,
+    /// Index of the project shown.
+    pub active: usize,
+    pub view: View,
+    pub focus: Focus,
+    /// Rows the details pane is scrolled down by.
+    pub details_scroll: u16,
+    /// Rows the help popup is scrolled down by.
+    pub help_scroll: u16,
+    /// Task the scroll offset belongs to, as its project, view and index.
+    pub details_of: Option<(usize, View, usize)>,
+    /// Only tasks containing this text, ignoring case, are shown.
+    pub filter: Option<String>,
+    /// Whether done tasks are left out of the list.
+    pub hide_done: bool,
+    pub sort: SortMode,
+    /// File the list is loaded from and saved to.
+    pub path: PathBuf,
+    /// Whether the task being created goes under the selected one.
+    pub subtask: bool,
+    /// Whether the lists have committed changes not yet written to `path`.
+    pub dirty: bool,
+    /// Whether changes are kept from being written to `path`.
+    pub read_only: bool,
+    /// Bumped by every state change, so unchanged frames can be skipped.
+    pub generation: u64,
+}
+
+impl App {
+    pub fn new() -> Self {
+        App {
+            popup: None,
+            input_mode: InputMode::Normal,
+            input_error: None,
+            cursor: None,
+            input: vec![String::new(), String::new(), String::new()],
+            insert_position: InsertPosition::BelowSelection,
+            numbers: NumberMode::None,
+            checkbox: Checkbox::Check,
+            pending: None,
+            last_nav: None,
+            wrap_when_held: false,
+            attachment: 0,
+            plan: None,
+            power: Power::default(),
+            status: None,
+            rng: Rng::from_time(),
+            last_pick: None,
+            flash: None,
+            projects: vec![Project::new(String::from(storage::DEFAULT_LIST))],
+            active: 0,
+            view: View::Tasks,
+            focus: Focus::List,
+            details_scroll: 0,
+            help_scroll: 0,
+            details_of: None,
+            filter: None,
+            hide_done: false,
+            sort: SortMode::Manual,
+            path: storage::default_path(),
+            subtask: false,
+            dirty: false,
+            read_only: false,
+            generation: 0,
+        }
+    }
+
+    /// Moves the selection `steps` rows down or up, wrapping around the ends
+    /// of the list. When `wrap_when_held` is off it only wraps on a
+    /// deliberate press, so holding `j` or `k` stops at the last or first
+    /// task instead of looping.
+    fn navigate(&mut self, down: bool, steps: usize, held: bool) {
+        let now = Instant::now();
+        let held = held
+            || self
+                .last_nav
+                .is_some_and(|(dir, at)| dir == down && now.duration_since(at) < HELD_WINDOW);
+        self.last_nav = Some((down, now));
+        let visible = self.visible();
+        if visible.is_empty() {
+            return;
+        }
+
+        let before = self.shown().state.selected();
+        let mut row = before.and_then(|s| visible.iter().position(|&i| i == s));
+        for _ in 0..steps {
+            let at_end = match row {
+                Some(n) if down => n + 1 >= visible.len(),
+                Some(n) => n == 0,
+                None => false,
+            };
+            if at_end && held && !self.wrap_when_held {
+                break;
+            }
+            row = Some(match row {
+                Some(n) if !at_end && down => n + 1,
+                Some(n) if !at_end => n - 1,
+                Some(_) if !down => visible.len() - 1,
+                _ => 0,
+            });
+        }
+        let selected = row.map(|n| visible[n]);
+        if selected != before {
+            self.shown_mut().state.select(selected);
+            self.touch();
+        }
+    }
+
+    fn project(&self) -> &Project {
+        &self.projects[self.active]
+    }
+
+    fn project_mut(&mut self) -> &mut Project {
+        &mut self.projects[self.active]
+    }
+
+    /// List of the current view.
+    fn shown(&self) -> &StateFullList<Task> {
+        match self.view {
+            View::Tasks => &self.project().list,
+            View::Archive => &self.project().archive,
+        }
+    }
+
+    fn shown_mut(&mut self) -> &mut StateFullList<Task> {
+        match self.view {
+            View::Tasks => &mut self.project_mut().list,
+            View::Archive => &mut self.project_mut().archive,
+        }
+    }
+
+    /// Indices of the tasks shown in the current view, in display order.
+    /// Hiding done tasks and sorting only apply to the task list.
+    fn visible(&self) -> Vec<usize> {
+        let query = self
+            .filter
+            .as_deref()
+            .filter(|query| !query.is_empty())
+            .map(str::to_lowercase);
+        let tasks = self.view == View::Tasks;
+        let items = &self.shown().items;
+        let mut visible: Vec<usize> = (0..items.len())
+            .filter(|&i| !(tasks && self.hide_done && items[i].done))
+            .filter(|&i| query.as_deref().is_none_or(|q| matches_query(&items[i], q)))
+            .collect();
+        if tasks && self.sort == SortMode::Priority {
+            // Subtasks sort with their parent, and the sort is stable, so
+            // families stay together and equal priorities keep their manual
+            // order.
+            let mut priority = Vec::with_capacity(items.len());
+            for (i, item) in items.iter().enumerate() {
+                priority.push(match item.subtask {
+                    true if i > 0 => priority[i - 1],
+                    _ => item.priority,
+                });
+            }
+            visible.sort_by(|&a, &b| priority[b].cmp(&priority[a]));
+        }
+        visible
+    }
+
+    /// Moves a selection that is no longer shown to the next shown task,
+    /// or the last one.
+    fn keep_selection_visible(&mut self) {
+        let Some(selected) = self.shown().state.selected() else {
+            return;
+        };
+        let visible = self.visible();
+        if !visible.contains(&selected) {
+            let next = visible.iter().find(|&&i| i > selected).or(visible.last());
+            self.shown_mut().state.select(next.copied());
+            self.touch();
+        }
+    }
+
+    /// Bindings of the current view, for the bottom bar and the help overlay.
+    fn bindings(&self) -> &'static [(&'static str, &'static str)] {
+        match self.view {
+            View::Tasks => NORMAL_HINTS,
+            View::Archive => ARCHIVE_HINTS,
+        }
+    }
+
+    /// Whether the selected task has anything to show in the details pane.
+    fn selected_has_details(&self) -> bool {
+        let shown = self.shown();
+        shown.state.selected().is_some_and(|i| {
+            let task = &shown.items[i];
+            task.details.is_some() || !task.attachments.is_empty() || task.due.is_some()
+        })
+    }
+
+    /// Scrolls the details pane back to the top when another task gets
+    /// selected, and leaves it once there is no pane to focus.
+    fn keep_details_scroll(&mut self) {
+        let selected = self
+            .shown()
+            .state
+            .selected()
+            .map(|i| (self.active, self.view, i));
+        if selected != self.details_of {
+            self.details_of = selected;
+            self.details_scroll = 0;
+            self.touch();
+        }
+        if self.focus == Focus::Details && !self.selected_has_details() {
+            self.focus = Focus::List;
+            self.touch();
+        }
+    }
+
+    /// Selects a random shown pending task and flashes it. The previous pick is
+    /// left out whenever another pending task is available.
+    fn pick_random(&mut self) {
+        let pending: Vec<usize> = self
+            .visible()
+            .into_iter()
+            .filter(|&i| !self.project().list.items[i].done)
+            .collect();
+        let pool: Vec<usize> = match pending.len() {
+            0 => return,
+            1 => pending,
+            _ => pending
+                .into_iter()
+                .filter(|&i| Some(i) != self.last_pick)
+                .collect(),
+        };
+        let i = pool[self.rng.below(pool.len())];
+        self.project_mut().list.state.select(Some(i));
+        self.last_pick = Some(i);
+        self.flash = Some(Instant::now());
+        self.touch();
+    }
+
+    /// Index of the selected task if it may be changed. A locked task is
+    /// refused with a message naming it.
+    fn selected_editable(&mut self) -> Option<usize> {
+        let i = self.project().list.state.selected()?;
+        let task = &self.project().list.items[i];
+        if task.locked {
+            self.status = Some(format!(
+                "\"{}\" is locked, unlock it with L first",
+                task.msg
+            ));
+            self.touch();
+            return None;
+        }
+        Some(i)
+    }
+
+    /// Remembers an action just applied to the list, for undo and saving.
+    fn record(&mut self, action: Action) {
+        if let Some(eviction) = self.project_mut().history.record(action) {
+            self.status = Some(match eviction {
+                Eviction::Count => format!(
+                    "Undo keeps the last {} changes, older ones are forgotten",
+                    history::LIMIT
+                ),
+                Eviction::Size => String::from("Undo history is full, older changes are forgotten"),
+            });
+            self.touch();
+        }
+        self.dirty = true;
+    }
+
+    /// Reverts the last action, or applies again the last undone one.
+    fn undo(&mut self, redo: bool) {
+        let done = if redo {
+            let project = self.project_mut();
+            project
+                .history
+                .redo(&mut project.list, &mut project.archive)
+        } else {
+            let project = self.project_mut();
+            project
+                .history
+                .undo(&mut project.list, &mut project.archive)
+        };
+        if done {
+            self.dirty = true;
+        } else {
+            self.status = Some(String::from(if redo {
+                "Nothing to redo"
+            } else {
+                "Nothing to undo"
+            }));
+        }
+        self.touch();
+    }
+
+    /// Raises or lowers the priority of the selected task.
+    fn change_priority(&mut self, up: bool) {
+        self.set_priority(|priority| {
+            if up {
+                priority.raise()
+            } else {
+                priority.lower()
+            }
+        });
+    }
+
+    /// Gives the selected task the priority `change` makes of its current
+    /// one.
+    fn set_priority(&mut self, change: impl FnOnce(Priority) -> Priority) {
+        let Some(i) = self.selected_editable() else {
+            return;
+        };
+        let old = self.project().list.items[i].clone();
+        let priority = change(old.priority);
+        if priority != old.priority {
+            self.project_mut().list.items[i].priority = priority;
+            self.record(Action::Edited(i, old));
+            self.touch();
+        }
+    }
+
+    /// Moves the selected task one row down or up, stopping at the ends of
+    /// the list rather than wrapping like the cursor does. A task moves with
+    /// its subtasks past the whole next task, and a subtask only moves among
+    /// its siblings. Only the manual order can be rearranged.
+    fn move_selected(&mut self, down: bool) {
+        let Some(i) = self.project().list.state.selected() else {
+            return;
+        };
+        if self.sort != SortMode::Manual {
+            self.status = Some(String::from(
+                "Switch back to manual order with p to move tasks",
+            ));
+            self.touch();
+            return;
+        }
+        let visible = self.visible();
+        let Some(row) = visible.iter().position(|&v| v == i) else {
+            return;
+        };
+        let rows = &self.project().list.items;
+        let family = task::family(rows, i);
+        if rows[i].subtask {
+            let target = if down {
+                visible.get(row + 1)
+            } else {
+                row.checked_sub(1).map(|row| &visible[row])
+            };
+            if let Some(&target) = target.filter(|&&t| family.contains(&t) && t != family.start) {
+                self.project_mut().list.swap(i, target);
+                self.record(Action::Moved(i, target));
+                self.touch();
+            }
+            return;
+        }
+        let to = if down {
+            visible
+                .iter()
+                .find(|&&v| v >= family.end)
+                .map(|&v| task::family(rows, v).end - family.len())
+        } else {
+            visible[..row].last().map(|&v| task::family(rows, v).start)
+        };
+        if let Some(to) = to {
+            let moved = self.project_mut().list.remove_rows(family.clone());
+            self.project_mut().list.insert_rows(to, moved);
+            self.record(Action::MovedRows(i, family.len(), to));
+            self.touch();
+        }
+    }
+
+    /// Whether the file is open read-only, reporting that nothing can be
+    /// changed then.
+    fn refuse_read_only(&mut self) -> bool {
+        if self.read_only {
+            self.status = Some(String::from(
+                "Read-only: restart without --no-migrate to upgrade and edit",
+            ));
+            self.touch();
+        }
+        self.read_only
+    }
+
+    /// Whether the selected row has subtasks, reporting that `what` cannot
+    /// be done to it then.
+    fn refuse_parent(&mut self, what: &str) -> bool {
+        let Some(i) = self.shown().state.selected() else {
+            return false;
+        };
+        let rows = &self.shown().items;
+        if rows[i].subtask || task::family(rows, i).len() == 1 {
+            return false;
+        }
+        self.status = Some(format!("{} its subtasks first", what));
+        self.touch();
+        true
+    }
+
+    /// Moves the done tasks, with their subtasks, below the pending ones,
+    /// keeping the order within each group.
+    fn sort_done_last(&mut self) {
+        let rows = &self.project().list.items;
+        let mut families = Vec::new();
+        let mut start = 0;
+        while start < rows.len() {
+            let family = task::family(rows, start);
+            start = family.end;
+            families.push(family);
+        }
+        families.sort_by_key(|family| rows[family.start].done);
+        let order: Vec<usize> = families.into_iter().flatten().collect();
+        if order.iter().enumerate().any(|(k, &i)| k != i) {
+            self.project_mut().list.permute(&order);
+            self.record(Action::Reordered(order));
+        }
+        self.touch();
+    }
+
+    /// Selects the first shown row, or the last one, if there is any.
+    fn select_edge(&mut self, last: bool) {
+        let visible = self.visible();
+        let edge = if last {
+            visible.last()
+        } else {
+            visible.first()
+        };
+        if edge.is_some() && self.shown().state.selected() != edge.copied() {
+            self.shown_mut().state.select(edge.copied());
+            self.touch();
+        }
+    }
+
+    /// Applies the planned bulk changes to the list as one undoable change
+    /// and says what they did.
+    fn apply_plan(&mut self) {
+        let Some(plan) = self.plan.take() else {
+            return;
+        };
+        let old = &self.project().list.items;
+        self.status = Some(match plan.apply(old) {
+            None => String::from("The list changed since the plan was made, nothing was applied"),
+            Some((_, summary)) if summary.is_empty() => String::from("Nothing to change"),
+            Some((rows, summary)) => {
+                let old = old.clone();
+                let list = &mut self.project_mut().list;
+                list.items = rows;
+                let selected = list.state.selected();
+                list.state.select(match list.items.len() {
+                    0 => None,
+                    len => selected.map(|s| s.min(len - 1)),
+                });
+                self.record(Action::Replaced(old));
+                format!(
+                    "Moved {}, marked {} done, deleted {}",
+                    summary.moved, summary.done, summary.dropped
+                )
+            }
+        });
+        self.close_input();
+    }
+
+    /// Removes the selected task of the shown list.
+    fn delete_selected(&mut self) {
+        let Some(i) = self.shown().state.selected() else {
+            return;
+        };
+        if let Some(task) = self.shown_mut().remove_selected() {
+            self.record(match self.view {
+                View::Tasks => Action::Deleted(i, task),
+                View::Archive => Action::ArchiveDeleted(i, task),
+            });
+        }
+    }
+
+    /// Shows the list `steps` after the shown one, going around.
+    fn cycle_project(&mut self, steps: usize) {
+        self.active = (self.active + steps) % self.projects.len();
+        self.touch();
+    }
+
+    /// Typed list name, trimmed, if no other list has it. A refused name
+    /// sets `input_error`.
+    fn list_name_input(&mut self, renaming: bool) -> Option<String> {
+        let name = self.input[0].trim().to_string();
+        if name.is_empty() {
+            return None;
+        }
+        let taken = self
+            .projects
+            .iter()
+            .enumerate()
+            .any(|(i, project)| project.name == name && !(renaming && i == self.active));
+        if taken {
+            self.input_error = Some(format!("\"{}\" already exists", name));
+            self.touch();
+            return None;
+        }
+        Some(name)
+    }
+
+    /// Moves the selected done task and its subtasks to the top of the
+    /// archive.
+    fn archive_selected(&mut self) {
+        let Some(i) = self.selected_editable() else {
+            return;
+        };
+        let family = task::family(&self.project().list.items, i);
+        if self.project().list.items[i].subtask {
+            self.status = Some(String::from("Subtasks are archived with their task"));
+        } else if !self.project().list.items[family.clone()]
+            .iter()
+            .all(|t| t.done)
+        {
+            self.status = Some(String::from("Only done tasks can be archived"));
+        } else {
+            let rows = self.project_mut().list.remove_rows(family.clone());
+            self.project_mut().archive.insert_rows(0, rows);
+            self.record(Action::Archived(i, 0, family.len()));
+        }
+        self.touch();
+    }
+
+    /// Moves the selected archived task and its subtasks back to the end of
+    /// the task list.
+    fn restore_selected(&mut self) {
+        let Some(a) = self.project().archive.state.selected() else {
+            return;
+        };
+        if self.project().archive.items[a].subtask {
+            self.status = Some(String::from("Subtasks are restored with their task"));
+        } else {
+            let family = task::family(&self.project().archive.items, a);
+            let rows = self.project_mut().archive.remove_rows(family.clone());
+            let i = self.project().list.items.len();
+            self.project_mut().list.insert_rows(i, rows);
+            self.record(Action::Restored(a, i, family.len()));
+        }
+        self.touch();
+    }
+
+    /// Buffer receiving typed characters, if any input is being edited.
+    fn active_input(&mut self) -> Option<&mut String> {
+        match (&self.input_mode, self.popup) {
+            (InputMode::Inline, _)
+            | (_, Some(Popup::NewTaskName))
+            | (_, Some(Popup::EditTaskName(_)))
+            | (_, Some(Popup::AttachmentPath))
+            | (_, Some(Popup::ExportPath))
+            | (_, Some(Popup::NewList))
+            | (_, Some(Popup::RenameList)) => Some(&mut self.input[0]),
+            (_, Some(Popup::NewTaskDetails)) | (_, Some(Popup::EditTaskDetails(_))) => {
+                Some(&mut self.input[1])
+            }
+            (_, Some(Popup::NewTaskDue)) => Some(&mut self.input[2]),
+            (InputMode::Search, _) => self.filter.as_mut(),
+            _ => None,
+        }
+    }
+
+    /// Typed details, a blank entry meaning no details.
+    fn details_input(&self) -> Option<String> {
+        if self.input[1].is_empty() {
+            None
+        } else {
+            Some(self.input[1].clone())
+        }
+    }
+
+    /// Maximum number of characters the active input accepts.
+    fn input_limit(&self) -> Option<usize> {
+        match (&self.input_mode, self.popup) {
+            (InputMode::Inline, _)
+            | (_, Some(Popup::NewTaskName))
+            | (_, Some(Popup::EditTaskName(_))) => Some(MAX_TITLE_LEN),
+            _ => None,
+        }
+    }
+
+    /// Leaves any popup or inline editor and discards the typed input.
+    fn close_input(&mut self) {
+        self.input_mode = InputMode::Normal;
+        self.popup = None;
+        for input in &mut self.input {
+            input.clear();
+        }
+        self.input_error = None;
+        self.cursor = None;
+        self.subtask = false;
+        self.plan = None;
+        self.touch();
+    }
+
+    /// Warns, when saving details typed or pasted in, that they are large
+    /// enough to bloat the task file.
+    fn warn_large_details(&mut self) {
+        let size = self.input[1].len();
+        if size > view::LARGE_DETAILS {
+            self.status = Some(format!(
+                "Saved {} of details, large details bloat the task file",
+                view::human_size(size)
+            ));
+        }
+    }
+
+    /// Every list with its archive, as saved.
+    fn saved(&self) -> Vec<storage::TaskList> {
+        self.projects.iter().map(Project::saved).collect()
+    }
+
+    /// Writes the list and the archive to disk.
+    fn save(&self) -> io::Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+        storage::save(&self.path, &self.saved())
+    }
+
+    /// Asks where to export the lists, next to the task file by default.
+    fn ask_export_path(&mut self) {
+        self.input[0] = export::default_path(&self.path).display().to_string();
+        self.popup = Some(Popup::ExportPath);
+        self.input_mode = InputMode::Editing;
+        self.touch();
+    }
+
+    /// Writes every list as a Markdown checklist to `path`, as `todo-tui
+    /// export` does.
+    fn export(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, export::lists(&self.saved()))
+    }
+
+    /// Writes the lists to disk if they have unsaved committed changes. A
+    /// failure is reported in the status bar and retried on the next call.
+    fn maybe_save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        match self.save() {
+            Ok(()) => self.dirty = false,
+            Err(e) => {
+                self.status = Some(format!("Could not save {}: {}", self.path.display(), e));
+                self.touch();
+            }
+        }
+    }
+
+    /// Drops the status message, which lasts until the next key press.
+    fn clear_status(&mut self) {
+        if self.status.take().is_some() {
+            self.touch();
+        }
+    }
+
+    /// Marks the state as changed so the next loop iteration redraws.
+    fn touch(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+}
+
+pub fn start_ui(options: Options) -> Result<(), Box<dyn Error>> {
+    // Loaded before entering the alternate screen, so a corrupt file is
+    // reported on the regular terminal and is never overwritten on exit.
+    let mut app = App::new();
+    if let Some(path) = options.path {
+        app.path = path;
+    }
+    app.power.low = options.low_power;
+    app.wrap_when_held = options.wrap_held;
+    if options.brackets {
+        app.checkbox = Checkbox::Brackets;
+    }
+    let loaded = storage::load(&app.path)?;
+    if !loaded.lists.is_empty() {
+        app.projects = loaded.lists.into_iter().map(Project::load).collect();
+    }
+    if loaded.legacy && options.no_migrate {
+        app.read_only = true;
+    } else if loaded.legacy {
+        app.popup = Some(Popup::Upgrade);
+    }
+
+    // A panic would otherwise leave the terminal raw, on the alternate
+    // screen and titled after the app. The hook found here is put back on
+    // exit, as an app embedding the UI goes on running.
+    let previous = Arc::new(panic::take_hook());
+    let hook = Arc::clone(&previous);
+    panic::set_hook(Box::new(move |info| {
+        let mut stdout = io::stdout();
+        let _ = disable_raw_mode();
+        let _ = execute!(stdout, LeaveAlternateScreen, DisableMouseCapture);
+        let _ = title::restore(&mut stdout);
+        hook(info);
+    }));
+    let res = run_terminal(&mut app);
+    let _ = panic::take_hook();
+    panic::set_hook(Box::new(move |info| previous(info)));
+    res
+}
+
+/// Runs `app` on the alternate screen, leaving the terminal as it found it.
+fn run_terminal(app: &mut App) -> Result<(), Box<dyn Error>> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    title::save(&mut stdout)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let res = run_app(&mut terminal, app);
+
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    title::restore(terminal.backend_mut())?;
+    terminal.show_cursor()?;
+
+    if let Err(e) = res {
+        println!("UI Crashed:\n{:#?}", e);
+    }
+    Ok(())
+}
+
+fn run_app<B: Backend + Write>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
+    let mut events = Events::new(app.power.tick_rate());
+    let mut redraw = Redraw::default();
+    let mut title = None;
+    let mut last_title: Option<Instant> = None;
+    loop {
+        app.keep_selection_visible();
+        app.keep_details_scroll();
+        if app.power.save_at_once() {
+            app.maybe_save();
+        }
+        events.set_tick_rate(app.power.tick_rate());
+        if redraw.needed(app.generation) {
+            terminal.draw(|f| ui(f, app))?;
+            redraw.done(app.generation);
+        }
+        // A change within the interval waits for a later tick, so bursts
+        // of changes send a single title.
+        let new_title = window_title(app, Local::now().date_naive());
+        if title.as_ref() != Some(&new_title)
+            && last_title.is_none_or(|at| at.elapsed() >= TITLE_INTERVAL)
+        {
+            title::set(terminal.backend_mut(), &new_title)?;
+            title = Some(new_title);
+            last_title = Some(Instant::now());
+        }
+        match events.next().map_err(io::Error::other)? {
+            AppEvent::Input(key) => {
+                if handle_input(app, &mut events, key) {
+                    return app.save();
+                }
+            }
+            AppEvent::Tick => {
+                app.maybe_save();
+                if expire_pending(app) {
+                    return app.save();
+                }
+                if app.flash.is_some_and(|at| at.elapsed() >= FLASH_DURATION) {
+                    app.flash = None;
+                    app.touch();
+                }
+                redraw.keepalive(app.power);
+            }
+            AppEvent::Resize => redraw.force(),
+            AppEvent::InputFailed(e) => return Err(e),
+        }
+    }
+}
+
+/// When the event loop draws: only once the app state changed, on resizes
+/// and at least once per keepalive of the power mode.
+#[derive(Debug, Default)]
+struct Redraw {
+    /// Generation of the app last drawn, `None` to draw whatever changed.
+    drawn: Option<u64>,
+    last: Option<Instant>,
+}
+
+impl Redraw {
+    fn needed(&self, generation: u64) -> bool {
+        self.drawn != Some(generation)
+    }
+
+    fn done(&mut self, generation: u64) {
+        self.drawn = Some(generation);
+        self.last = Some(Instant::now());
+    }
+
+    fn force(&mut self) {
+        self.drawn = None;
+    }
+
+    /// Forces the next draw if the screen went without one for longer than
+    /// `power` allows.
+    fn keepalive(&mut self, power: Power) {
+        let due = |keepalive| self.last.is_none_or(|at| at.elapsed() >= keepalive);
+        if power.keepalive().is_some_and(due) {
+            self.force();
+        }
+    }
+}
+
+/// Applies a key press to `app`, along with the presses of the same
+/// navigation key queued behind it, returning `true` when the app should
+/// quit.
+fn handle_input(app: &mut App, events: &mut Events, key: KeyEvent) -> bool {
+    match navigation(app, key) {
+        Some(down) => {
+            app.clear_status();
+            let steps = 1 + events.take_repeats(key);
+            app.navigate(down, steps, steps > 1);
+            false
+        }
+        None => handle_key(app, key),
+    }
+}
+
+/// Direction of `key` if it is a plain navigation key in normal mode, which
+/// the event loop collapses with its queued repeats.
+fn navigation(app: &App, key: KeyEvent) -> Option<bool> {
+    if !matches!(app.input_mode, InputMode::Normal)
+        || app.pending.is_some()
+        || app.popup.is_some()
+        || app.focus == Focus::Details
+    {
+        return None;
+    }
+    match key.code {
+        KeyCode::Char('j') => Some(true),
+        KeyCode::Char('k') => Some(false),
+        _ => None,
+    }
+}
+
+/// Applies a key press to `app`, returning `true` when the app should quit.
+fn handle_key(app: &mut App, key: KeyEvent) -> bool {
+    app.clear_status();
+    match app.input_mode {
+        InputMode::Normal => {
+            match app.popup {
+                Some(Popup::Attachments) => {
+                    attachments_key(app, key.code);
+                    return false;
+                }
+                Some(Popup::ConfirmDelete) | Some(Popup::ConfirmLock) => {
+                    confirm_key(app, key.code);
+                    return false;
+                }
+                Some(Popup::Upgrade) => return upgrade_key(app, key.code),
+                Some(Popup::Reorder) => {
+                    reorder_key(app, key.code);
+                    return false;
+                }
+                Some(Popup::Help) => {
+                    match key.code {
+                        KeyCode::Char('?') | KeyCode::Char('q') | KeyCode::Esc => {
+                            app.close_input();
+                        }
+                        KeyCode::Char('j') | KeyCode::Down => {
+                            app.help_scroll = app.help_scroll.saturating_add(1);
+                            app.touch();
+                        }
+                        KeyCode::Char('k') | KeyCode::Up => {
+                            app.help_scroll = app.help_scroll.saturating_sub(1);
+                            app.touch();
+                        }
+                        _ => {}
+                    }
+                    return false;
+                }
+                _ => {}
+            }
+            if app.focus == Focus::Details {
+                details_key(app, key.code);
+                return false;
+            }
+            if let Some((prefix, _)) = app.pending.take() {
+                app.touch();
+                if let KeyCode::Char(c) = key.code {
+                    let found = SEQUENCES.iter().find(|s| s.0 == prefix && s.1 == c);
+                    if let Some(&(_, _, action, _)) = found {
+                        run_sequence(app, action);
+                    }
+                }
+                return false;
+            }
+            if let KeyCode::Char(c) = key.code {
+                if SEQUENCES.iter().any(|s| s.0 == c) {
+                    app.pending = Some((c, Instant::now()));
+                    app.touch();
+                    return false;
+                }
+            }
+            if changes_tasks(key) && app.refuse_read_only() {
+                return false;
+            }
+            if key.code == KeyCode::Char('r') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                app.undo(true);
+                return false;
+            }
+            if key.code == KeyCode::Char('e') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                app.ask_export_path();
+                return false;
+            }
+            return normal_key(app, key.code);
+        }
+        InputMode::Search if matches!(key.code, KeyCode::Enter | KeyCode::Esc) => {
+            if key.code == KeyCode::Esc || app.filter.as_deref() == Some("") {
+                app.filter = None;
+            }
+            app.input_mode = InputMode::Normal;
+            app.cursor = None;
+            app.touch();
+        }
+        InputMode::Editing | InputMode::Inline | InputMode::Search => match key.code {
+            KeyCode::Char(c) => {
+                let limit = app.input_limit();
+                let cursor = app.cursor;
+                if let Some(input) = app.active_input() {
+                    if limit.is_none_or(|limit| input.chars().count() < limit) {
+                        let at = cursor_in(input, cursor);
+                        input.insert(at, c);
+                        app.cursor = Some(at + c.len_utf8());
+                        app.input_error = None;
+                        app.touch();
+                    }
+                }
+            }
+            KeyCode::Backspace | KeyCode::Delete => {
+                let cursor = app.cursor;
+                if let Some(input) = app.active_input() {
+                    let at = cursor_in(input, cursor);
+                    let removed = if key.code == KeyCode::Backspace {
+                        input[..at]
+                            .graphemes(true)
+                            .next_back()
+                            .map(|g| at - g.len()..at)
+                    } else {
+                        input[at..].graphemes(true).next().map(|g| at..at + g.len())
+                    };
+                    if let Some(removed) = removed {
+                        let start = removed.start;
+                        input.replace_range(removed, "");
+                        app.cursor = Some(start);
+                        app.input_error = None;
+                        app.touch();
+                    }
+                }
+            }
+            KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End => {
+                let cursor = app.cursor;
+                if let Some(input) = app.active_input() {
+                    let at = cursor_in(input, cursor);
+                    app.cursor = match key.code {
+                        KeyCode::Left => input[..at]
+                            .graphemes(true)
+                            .next_back()
+                            .map(|g| at - g.len()),
+                        KeyCode::Right => input[at..].graphemes(true).next().map(|g| at + g.len()),
+                        KeyCode::Home => Some(0),
+                        _ => None,
+                    }
+                    .or(Some(at))
+                    .filter(|&at| at < input.len());
+                    app.touch();
+                }
+            }
+            KeyCode::Esc => match app.popup {
+                Some(Popup::AttachmentPath) => open_attachments(app),
+                _ => app.close_input(),
+            },
+            KeyCode::Enter => {
+                app.cursor = None;
+                if let InputMode::Inline = app.input_mode {
+                    if let Some(i) = app.project().list.state.selected() {
+                        if !app.input[0].trim().is_empty() {
+                            let old = app.project().list.items[i].clone();
+                            app.project_mut().list.items[i].msg = app.input[0].clone();
+                            app.record(Action::Edited(i, old));
+                        }
+                    }
+                    app.close_input();
+                } else if let Some(popup) = app.popup {
+                    match popup {
+                        Popup::NewTaskName => {
+                            if app.input[0].trim().is_empty() {
+                                app.input_error = Some(String::from("the title is empty"));
+                            } else {
+                                app.popup = Some(Popup::NewTaskDetails);
+                            }
+                            app.touch();
+                        }
+                        Popup::NewTaskDetails => {
+                            app.popup = Some(Popup::NewTaskDue);
+                            app.touch();
+                        }
+                        Popup::NewTaskDue => {
+                            let input = app.input[2].trim();
+                            let due = if input.is_empty() {
+                                None
+                            } else {
+                                match due::parse(input, Local::now().date_naive()) {
+                                    Some(date) => Some(date),
+                                    None => {
+                                        app.input_error =
+                                            Some(format!("cannot read \"{}\"", input));
+                                        app.touch();
+                                        return false;
+                                    }
+                                }
+                            };
+                            let mut task = Task::new(app.input[0].clone(), app.details_input());
+                            task.due = due;
+                            let rows = &app.project().list.items;
+                            let selected = app.project().list.state.selected();
+                            let mut old = None;
+                            let index = match selected {
+                                Some(i) if app.subtask => {
+                                    task.subtask = true;
+                                    let family = task::family(rows, i);
+                                    // Reopening the task too takes the whole
+                                    // list to undo.
+                                    old = rows[family.start].done.then(|| rows.clone());
+                                    family.end
+                                }
+                                _ => app.insert_position.index(rows, selected),
+                            };
+                            let list = &mut app.project_mut().list;
+                            list.insert(index, task);
+                            task::set_done(&mut list.items, index, false);
+                            app.record(match old {
+                                Some(old) => Action::Replaced(old),
+                                None => Action::Added(index),
+                            });
+                            app.warn_large_details();
+                            app.close_input();
+                        }
+                        Popup::EditTaskName(i) => {
+                            if !app.input[0].trim().is_empty() && i < app.project().list.items.len()
+                            {
+                                let old = app.project().list.items[i].clone();
+                                app.project_mut().list.items[i].msg = app.input[0].clone();
+                                app.record(Action::Edited(i, old));
+                            }
+                            app.close_input();
+                        }
+                        Popup::EditTaskDetails(i) => {
+                            if i < app.project().list.items.len() {
+                                let old = app.project().list.items[i].clone();
+                                app.project_mut().list.items[i].details = app.details_input();
+                                app.record(Action::Edited(i, old));
+                                app.warn_large_details();
+                            }
+                            app.close_input();
+                        }
+                        Popup::AttachmentPath => {
+                            if let Some(i) = app.project().list.state.selected() {
+                                let path = app.input[0].trim().to_string();
+                                if !path.is_empty() {
+                                    let old = app.project().list.items[i].clone();
+                                    let attachments =
+                                        &mut app.project_mut().list.items[i].attachments;
+                                    attachments.push(PathBuf::from(path));
+                                    app.attachment = attachments.len() - 1;
+                                    app.record(Action::Edited(i, old));
+                                }
+                            }
+                            open_attachments(app);
+                        }
+                        Popup::NewList => {
+                            if let Some(name) = app.list_name_input(false) {
+                                app.projects.push(Project::new(name));
+                                app.active = app.projects.len() - 1;
+                                app.view = View::Tasks;
+                                app.dirty = true;
+                                app.close_input();
+                            }
+                        }
+                        Popup::RenameList => {
+                            if let Some(name) = app.list_name_input(true) {
+                                app.project_mut().name = name;
+                                app.dirty = true;
+                                app.close_input();
+                            }
+                        }
+                        Popup::ExportPath => {
+                            let input = app.input[0].trim();
+                            let path = attachment::expand_tilde(Path::new(input));
+                            if input.is_empty() {
+                                app.input_error = Some(String::from("the path is empty"));
+                            } else if let Err(e) = app.export(&path) {
+                                app.input_error = Some(e.to_string());
+                            } else {
+                                app.close_input();
+                                app.status = Some(format!("Exported to {}", path.display()));
+                            }
+                            app.touch();
+                        }
+                        Popup::Attachments
+                        | Popup::ConfirmDelete
+                        | Popup::ConfirmLock
+                        | Popup::Help
+                        | Popup::Upgrade
+                        | Popup::Reorder => {}
+                    }
+                }
+            }
+            _ => {}
+        },
+    }
+    false
+}
+
+/// Applies a single key binding of normal mode.
+fn normal_key(app: &mut App, code: KeyCode) -> bool {
+    if app.view == View::Archive && archive_key(app, code) {
+        return false;
+    }
+    match code {
+        KeyCode::Char('q') => return true,
+        KeyCode::Char('?') => {
+            app.popup = Some(Popup::Help);
+            app.help_scroll = 0;
+            app.touch();
+        }
+        KeyCode::Char('n') => {
+            app.popup = Some(Popup::NewTaskName);
+            app.input_mode = InputMode::Editing;
+            app.touch();
+        }
+        KeyCode::Char('o') if app.project().list.state.selected().is_some() => {
+            let list = &app.project().list;
+            if list
+                .state
+                .selected()
+                .is_some_and(|i| !task::can_reopen(&list.items, i))
+            {
+                app.status = Some(String::from("Its task is done and locked"));
+                app.touch();
+                return false;
+            }
+            app.subtask = true;
+            app.popup = Some(Popup::NewTaskName);
+            app.input_mode = InputMode::Editing;
+            app.touch();
+        }
+        KeyCode::Char('j') => app.navigate(true, 1, false),
+        KeyCode::Char('k') => app.navigate(false, 1, false),
+        // Reached once a lone g times out waiting for the second g.
+        KeyCode::Char('g') => app.select_edge(false),
+        KeyCode::Char('G') => app.select_edge(true),
+        KeyCode::Char('J') => app.move_selected(true),
+        KeyCode::Char('K') => app.move_selected(false),
+        KeyCode::Char('e') => {
+            if let Some(i) = app.selected_editable() {
+                app.input[0] = app.project().list.items[i].msg.clone();
+                app.popup = Some(Popup::EditTaskName(i));
+                app.input_mode = InputMode::Editing;
+                app.touch();
+            }
+        }
+        KeyCode::Char('E') => {
+            if let Some(i) = app.selected_editable() {
+                app.input[1] = app.project().list.items[i]
+                    .details
+                    .clone()
+                    .unwrap_or_default();
+                app.popup = Some(Popup::EditTaskDetails(i));
+                app.input_mode = InputMode::Editing;
+                app.touch();
+            }
+        }
+        KeyCode::Char('i') => {
+            if let Some(i) = app.selected_editable() {
+                app.input[0] = app.project().list.items[i].msg.clone();
+                app.input_mode = InputMode::Inline;
+                app.touch();
+            }
+        }
+        KeyCode::Char('I') => {
+            app.insert_position = app.insert_position.cycle();
+            app.touch();
+        }
+        KeyCode::Char('A') => {
+            app.view = match app.view {
+                View::Tasks => View::Archive,
+                View::Archive => View::Tasks,
+            };
+            app.touch();
+        }
+        KeyCode::Tab => app.cycle_project(1),
+        KeyCode::BackTab => app.cycle_project(app.projects.len() - 1),
+        KeyCode::Char('N') => {
+            app.popup = Some(Popup::NewList);
+            app.input_mode = InputMode::Editing;
+            app.touch();
+        }
+        KeyCode::Char('R') => {
+            app.input[0] = app.project().name.clone();
+            app.popup = Some(Popup::RenameList);
+            app.input_mode = InputMode::Editing;
+            app.touch();
+        }
+        KeyCode::Char('a') => app.archive_selected(),
+        KeyCode::Char('l') if app.selected_has_details() => {
+            app.focus = Focus::Details;
+            app.touch();
+        }
+        KeyCode::Char('c') => {
+            app.hide_done = !app.hide_done;
+            app.touch();
+        }
+        KeyCode::Char('/') => {
+            app.filter.get_or_insert_with(String::new);
+            app.input_mode = InputMode::Search;
+            app.touch();
+        }
+        KeyCode::Esc if app.filter.is_some() => {
+            app.filter = None;
+            app.touch();
+        }
+        KeyCode::Char('#') => {
+            app.numbers = app.numbers.cycle();
+            app.touch();
+        }
+        KeyCode::Char('r') => app.pick_random(),
+        KeyCode::Char('u') => app.undo(false),
+        KeyCode::Char('+') => app.change_priority(true),
+        KeyCode::Char('-') => app.change_priority(false),
+        KeyCode::Char('1') => app.set_priority(|_| Priority::High),
+        KeyCode::Char('2') => app.set_priority(|_| Priority::Normal),
+        KeyCode::Char('3') => app.set_priority(|_| Priority::Low),
+        KeyCode::Char('s') => app.sort_done_last(),
+        KeyCode::Char('B') => {
+            app.plan = Some(Plan::new(&app.project().list.items));
+            app.popup = Some(Popup::Reorder);
+            app.touch();
+        }
+        KeyCode::Char('Z') => {
+            app.power.low = !app.power.low;
+            app.status = Some(String::from(if app.power.low {
+                "Low power mode on, the screen only updates on changes"
+            } else {
+                "Low power mode off"
+            }));
+            app.touch();
+        }
+        KeyCode::Char('D') => {
+            let old = app.project().list.items.clone();
+            let (removed, locked) = app.project_mut().list.clear_done();
+            if removed > 0 {
+                app.record(Action::Replaced(old));
+            }
+            if removed > 0 {
+                app.status = Some(format!(
+                    "Cleared {}{}",
+                    count_tasks(removed, "done"),
+                    skipped_locked(locked)
+                ));
+            } else if locked > 0 {
+                app.status = Some(format!(
+                    "Nothing cleared, {} skipped",
+                    count_tasks(locked, "locked")
+                ));
+            }
+            app.touch();
+        }
+        KeyCode::Char('T') => {
+            let old = app.project().list.items.clone();
+            let (done, changed, locked) = app.project_mut().list.toggle_all();
+            if changed > 0 {
+                app.record(Action::Replaced(old));
+            }
+            let state = if done { "done" } else { "pending" };
+            if changed > 0 {
+                app.status = Some(format!(
+                    "Marked {} {}{}",
+                    count_tasks(changed, ""),
+                    state,
+                    skipped_locked(locked)
+                ));
+            } else if locked > 0 {
+                app.status = Some(format!(
+                    "Nothing marked {}, {} skipped",
+                    state,
+                    count_tasks(locked, "locked")
+                ));
+            }
+            app.touch();
+        }
+        KeyCode::Char('p') => {
+            app.sort = app.sort.toggle();
+            app.touch();
+        }
+        KeyCode::Char('@') if app.project().list.state.selected().is_some() => {
+            app.attachment = 0;
+            open_attachments(app);
+        }
+        KeyCode::Char('d') => {
+            let editable = app.selected_editable();
+            if let Some(i) = editable.filter(|_| !app.refuse_parent("Delete")) {
+                // A done task is deleted right away, undo being enough of a
+                // safety net for it.
+                if app.project().list.items[i].done {
+                    app.delete_selected();
+                    app.status = Some(String::from("Deleted, u to undo"));
+                } else {
+                    app.popup = Some(Popup::ConfirmDelete);
+                }
+            }
+            app.touch();
+        }
+        KeyCode::Char('L') if app.project().list.state.selected().is_some() => {
+            app.popup = Some(Popup::ConfirmLock);
+            app.touch();
+        }
+        KeyCode::Enter => {
+            if let Some(i) = app.selected_editable() {
+                let rows = &app.project().list.items;
+                let (done, completed_at) = (!rows[i].done, rows[i].completed_at);
+                let parent = task::family(rows, i).start;
+                // Reopening the task too takes the whole list to undo.
+                let old = (!done && parent != i && rows[parent].done).then(|| rows.clone());
+                if !task::set_done(&mut app.project_mut().list.items, i, done) {
+                    app.status = Some(String::from(if done {
+                        "Finish its subtasks first"
+                    } else {
+                        "Its task is locked"
+                    }));
+                    app.touch();
+                    return false;
+                }
+                app.record(match old {
+                    Some(old) => Action::Replaced(old),
+                    None => Action::Toggled(i, completed_at),
+                });
+                app.touch();
+            }
+        }
+        _ => {}
+    }
+    false
+}
+
+/// Applies the bindings specific to the archive view, returning `true` when
+/// `code` was taken. Keys changing tasks in place do nothing there.
+fn archive_key(app: &mut App, code: KeyCode) -> bool {
+    match code {
+        KeyCode::Enter => app.restore_selected(),
+        KeyCode::Char('d') => {
+            if app.project().archive.state.selected().is_some() && !app.refuse_parent("Delete") {
+                app.popup = Some(Popup::ConfirmDelete);
+                app.touch();
+            }
+        }
+        KeyCode::Char(
+            'n' | 'o' | 'J' | 'K' | 'e' | 'E' | 'i' | 'I' | 'c' | 'a' | 'r' | '+' | '-' | '1' | '2'
+            | '3' | 'p' | 's' | 'B' | 'D' | 'T' | '@' | 'L',
+        ) => {}
+        _ => return false,
+    }
+    true
+}
+
+/// `n` tasks, e.g. `1 done task` or `2 done tasks` for the adjective
+/// `done`, which may be empty.
+fn count_tasks(n: usize, adjective: &str) -> String {
+    let noun = if n == 1 { "task" } else { "tasks" };
+    if adjective.is_empty() {
+        format!("{} {}", n, noun)
+    } else {
+        format!("{} {} {}", n, adjective, noun)
+    }
+}
+
+/// End of a status message telling that `locked` tasks were skipped, empty
+/// when there were none.
+fn skipped_locked(locked: usize) -> String {
+    match locked {
+        0 => String::new(),
+        n => format!(", skipped {}", count_tasks(n, "locked")),
+    }
+}
+
+/// Applies a key press to the focused details pane. The offset is clamped
+/// to the content when the pane is drawn.
+fn details_key(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Char('j') | KeyCode::Down => {
+            app.details_scroll = app.details_scroll.saturating_add(1);
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            app.details_scroll = app.details_scroll.saturating_sub(1);
+        }
+        KeyCode::Char('h') | KeyCode::Char('l') | KeyCode::Esc => app.focus = Focus::List,
+        _ => return,
+    }
+    app.touch();
+}
+
+/// Shows the attachments popup of the selected task, leaving any input.
+fn open_attachments(app: &mut App) {
+    app.close_input();
+    app.popup = Some(Popup::Attachments);
+}
+
+/// Applies a key press to the confirmation asked about the selected task.
+fn confirm_key(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Char('y') | KeyCode::Enter => {
+            match (app.popup, app.shown().state.selected()) {
+                (Some(Popup::ConfirmDelete), Some(_)) => app.delete_selected(),
+                (Some(Popup::ConfirmLock), Some(i)) => {
+                    let old = app.project().list.items[i].clone();
+                    app.project_mut().list.items[i].locked = !old.locked;
+                    app.record(Action::Edited(i, old));
+                }
+                _ => {}
+            }
+            app.close_input();
+        }
+        KeyCode::Char('n') | KeyCode::Esc => app.close_input(),
+        _ => {}
+    }
+}
+
+/// Whether `key` changes tasks or lists in normal mode, in either view.
+fn changes_tasks(key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('r') => key.modifiers.contains(KeyModifiers::CONTROL),
+        _ if key.modifiers.contains(KeyModifiers::CONTROL) => false,
+        KeyCode::Enter => true,
+        KeyCode::Char(c) => "noJKeEiadu+-123sBDTLNR".contains(c),
+        _ => false,
+    }
+}
+
+/// Applies a key press to the upgrade prompt, returning `true` when the app
+/// should quit. The old file is copied before the upgraded lists are saved;
+/// declining quits without writing anything.
+fn upgrade_key(app: &mut App, code: KeyCode) -> bool {
+    match code {
+        KeyCode::Char('y') | KeyCode::Enter => {
+            match storage::upgrade(&app.path, &app.saved()) {
+                Ok(copy) => {
+                    app.status = Some(format!(
+                        "Upgraded, the old file is kept as {}",
+                        copy.display()
+                    ));
+                }
+                Err(e) => {
+                    app.read_only = true;
+                    app.status = Some(format!("Could not upgrade: {}", e));
+                }
+            }
+            app.close_input();
+        }
+        KeyCode::Char('n') | KeyCode::Char('q') | KeyCode::Esc => {
+            app.read_only = true;
+            return true;
+        }
+        _ => {}
+    }
+    false
+}
+
+/// Applies a key press to the attachments popup of the selected task.
+fn attachments_key(app: &mut App, code: KeyCode) {
+    let Some(i) = app.project().list.state.selected() else {
+        app.close_input();
+        return;
+    };
+    let changes = matches!(code, KeyCode::Char('a') | KeyCode::Char('d'));
+    if changes && (app.refuse_read_only() || app.selected_editable().is_none()) {
+        app.touch();
+        return;
+    }
+    let attachments = &mut app.projects[app.active].list.items[i].attachments;
+    match code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('@') => app.close_input(),
+        KeyCode::Char('a') => {
+            app.popup = Some(Popup::AttachmentPath);
+            app.input_mode = InputMode::Editing;
+        }
+        KeyCode::Char('j') => {
+            app.attachment = (app.attachment + 1).min(attachments.len().saturating_sub(1));
+        }
+        KeyCode::Char('k') => app.attachment = app.attachment.saturating_sub(1),
+        KeyCode::Char('d') if app.attachment < attachments.len() => {
+            let old = app.project().list.items[i].clone();
+            let attachments = &mut app.projects[app.active].list.items[i].attachments;
+            attachments.remove(app.attachment);
+            app.attachment = app.attachment.min(attachments.len().saturating_sub(1));
+            app.record(Action::Edited(i, old));
+        }
+        KeyCode::Char('o') | KeyCode::Enter => {
+            if let Some(path) = attachments.get(app.attachment) {
+                if let Err(e) = attachment::open(path) {
+                    app.status = Some(format!("Cannot open {}: {}", path.display(), e));
+                }
+            }
+        }
+        _ => {}
+    }
+    app.touch();
+}
+
+/// Applies a key press to the reorder popup.
+fn reorder_key(app: &mut App, code: KeyCode) {
+    let Some(plan) = app.plan.as_mut() else {
+        app.close_input();
+        return;
+    };
+    match code {
+        KeyCode::Esc | KeyCode::Char('q') => app.close_input(),
+        KeyCode::Enter => app.apply_plan(),
+        KeyCode::Char('j') | KeyCode::Down => {
+            plan.selected = (plan.selected + 1).min(plan.steps.len().saturating_sub(1));
+        }
+        KeyCode::Char('k') | KeyCode::Up => plan.selected = plan.selected.saturating_sub(1),
+        KeyCode::Char('J') => plan.move_selected(false),
+        KeyCode::Char('K') => plan.move_selected(true),
+        KeyCode::Char(c @ ('x' | 'd')) => {
+            let op = if c == 'x' { Op::Done } else { Op::Drop };
+            app.status = (!plan.toggle(op)).then(|| String::from("Locked tasks can only be moved"));
+        }
+        _ => {}
+    }
+    app.touch();
+}
+
+fn run_sequence(app: &mut App, action: SequenceAction) {
+    match action {
+        SequenceAction::SelectFirst => app.select_edge(false),
+    }
+}
+
+/// Drops a prefix key whose sequence timed out, firing the prefix's own
+/// binding if it has one. Returns `true` when that binding quits the app.
+fn expire_pending(app: &mut App) -> bool {
+    match app.pending {
+        Some((prefix, since)) if since.elapsed() >= SEQUENCE_TIMEOUT => {
+            app.pending = None;
+            app.touch();
+            normal_key(app, KeyCode::Char(prefix))
+        }
+        _ => false,
+    }
+}
+
+fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let chunks = base_layout(f);
+
+    let selected = app.shown().state.selected();
+    let (list_area, details_area) = if app.selected_has_details() {
+        let sub_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(chunks[0]);
+        (sub_chunks[0], Some(sub_chunks[1]))
+    } else {
+        (chunks[0], None)
+    };
+
+    let editing = match app.input_mode {
+        InputMode::Inline => Some((app.input[0].as_str(), cursor_in(&app.input[0], app.cursor))),
+        _ => None,
+    };
+    let visible = app.visible();
+    let query = app
+        .filter
+        .as_deref()
+        .filter(|query| !query.is_empty())
+        .map(str::to_lowercase);
+    let title = list_title(app);
+    let project = &mut app.projects[app.active];
+    let shown = match app.view {
+        View::Tasks => &mut project.list,
+        View::Archive => &mut project.archive,
+    };
+    let list = TaskListWidget::new(&shown.items)
+        .rows(&visible)
+        .format(RowFormat {
+            numbers: app.numbers,
+            checkbox: app.checkbox,
+        })
+        .editing(editing)
+        .query(query.as_deref())
+        .flash(app.flash.is_some())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(title)
+                .title_alignment(Alignment::Center),
+        );
+    f.render_stateful_widget(list, list_area, &mut shown.state);
+    if let Some((x, y)) = shown.state.cursor() {
+        f.set_cursor(x, y);
+    }
+
+    if let (Some(i), Some(area)) = (selected, details_area) {
+        let border = if app.focus == Focus::Details {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        let project = &app.projects[app.active];
+        let task = match app.view {
+            View::Tasks => &project.list.items[i],
+            View::Archive => &project.archive.items[i],
+        };
+        f.render_widget(
+            details_win(task, area, &mut app.details_scroll).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .border_style(border),
+            ),
+            area,
+        );
+    }
+
+    f.render_widget(stats_gauge(app), chunks[1]);
+    f.render_widget(command_helper(app, chunks[2].width), chunks[2]);
+
+    match (app.popup, selected) {
+        (Some(Popup::Attachments), Some(i)) => {
+            let attachments = &app.shown().items[i].attachments;
+            let height = u16::try_from(attachments.len().max(1))
+                .unwrap_or(u16::MAX)
+                .saturating_add(2);
+            let area = centered_rect(popup_width(f.size()), height, f.size());
+            let mut state = ListState::default();
+            state.select(Some(app.attachment).filter(|_| !attachments.is_empty()));
+            f.render_widget(Clear, area);
+            f.render_stateful_widget(attachments_popup(attachments), area, &mut state);
+        }
+        (Some(popup @ (Popup::ConfirmDelete | Popup::ConfirmLock)), Some(i)) => {
+            let task = &app.shown().items[i];
+            let (heading, question) = match popup {
+                Popup::ConfirmDelete if app.view == View::Archive => {
+                    ("Delete archived task", "Delete for good? (y/n)")
+                }
+                Popup::ConfirmDelete => ("Delete task", "Delete? (y/n)"),
+                _ if task.locked => ("Unlock task", "Unlock? (y/n)"),
+                _ => ("Lock task", "Lock? (y/n)"),
+            };
+            let width = popup_width(f.size());
+            let title = sanitize_line(&task.msg);
+            let height = wrapped_rows(&title, width.saturating_sub(2)).saturating_add(4);
+            let area = centered_rect(width, height, f.size());
+            f.render_widget(Clear, area);
+            f.render_widget(confirm_popup(heading, title, question), area);
+        }
+        (Some(Popup::Upgrade), _) => {
+            let message = format!(
+                "{} was written by an older version. Upgrading it keeps a copy as {}.pre-upgrade.",
+                app.path.display(),
+                app.path.display()
+            );
+            let width = popup_width(f.size());
+            let message = sanitize_line(&message);
+            let height = wrapped_rows(&message, width.saturating_sub(2)).saturating_add(4);
+            let area = centered_rect(width, height, f.size());
+            f.render_widget(Clear, area);
+            f.render_widget(
+                confirm_popup("Upgrade task file", message, "Upgrade? (y/n, n quits)"),
+                area,
+            );
+        }
+        (Some(Popup::Reorder), _) => {
+            if let Some(plan) = &app.plan {
+                let height = u16::try_from(plan.steps.len().max(1))
+                    .unwrap_or(u16::MAX)
+                    .saturating_add(2);
+                let area = centered_rect(popup_width(f.size()), height, f.size());
+                let mut state = ListState::default();
+                state.select(Some(plan.selected).filter(|_| !plan.steps.is_empty()));
+                f.render_widget(Clear, area);
+                f.render_stateful_widget(reorder_popup(plan), area, &mut state);
+            }
+        }
+        (Some(Popup::Help), _) => {
+            let width = popup_width(f.size());
+            let rows = help_rows(app.bindings(), width.saturating_sub(2));
+            let height = u16::try_from(rows.len())
+                .unwrap_or(u16::MAX)
+                .saturating_add(2);
+            let area = centered_rect(width, height, f.size());
+            f.render_widget(Clear, area);
+            f.render_widget(help_popup(rows, area, &mut app.help_scroll), area);
+        }
+        (Some(popup), _) => {
+            let width = popup_width(f.size());
+            let area = centered_rect(width, input_popup_height(app, popup, width), f.size());
+            f.render_widget(Clear, area);
+            f.render_widget(input_popup(app, popup), area);
+        }
+        _ => {}
+    }
+}
+
+/// The list, the statistics row and the bottom bar, from top to bottom.
+/// On a short terminal the bar is kept first, then the statistics.
+fn base_layout<B: Backend>(f: &Frame<B>) -> Vec<Rect> {
+    let area = f.size();
+    let bar = area.height.min(3);
+    let stats = area.height.saturating_sub(bar).min(1);
+    let list = area.height - bar - stats;
+    vec![
+        Rect::new(area.x, area.y, area.width, list),
+        Rect::new(area.x, area.y + list, area.width, stats),
+        Rect::new(area.x, area.y + list + stats, area.width, bar),
+    ]
+}
+
+/// Share of the tasks of the active list that are done, with the counts.
+fn stats_gauge(app: &App) -> Gauge<'static> {
+    let tasks = &app.project().list.items;
+    let done = tasks.iter().filter(|task| task.done).count();
+    let label = match tasks.len() {
+        0 => String::from("No tasks"),
+        total => format!("{} tasks, {} done, {} left", total, done, total - done),
+    };
+    Gauge::default()
+        .ratio(match tasks.len() {
+            0 => 0.0,
+            total => done as f64 / total as f64,
+        })
+        .label(label)
+        .gauge_style(Style::default().fg(Color::Green).bg(Color::Black))
+}
+
+/// Bottom bar of `width` columns: the hints of the current mode that fit,
+/// or the pending sequence, or the status message.
+fn command_helper(app: &App, width: u16) -> Paragraph<'static> {
+    if let Some((prefix, _)) = app.pending {
+        let mut hints: Vec<String> = SEQUENCES
+            .iter()
+            .filter(|s| s.0 == prefix)
+            .map(|(prefix, key, _, desc)| format!("{}{}: {}", prefix, key, desc))
+            .collect();
+        hints.push(String::from("Esc: cancel"));
+        return helper_bar(format!("{}- | {}", prefix, hints.join(" | ")));
+    }
+    if let Some(status) = &app.status {
+        return helper_bar(sanitize_line(status));
+    }
+    let hints = match app.input_mode {
+        InputMode::Normal => match app.popup {
+            Some(Popup::Attachments) => ATTACHMENT_HINTS,
+            Some(Popup::Help) => HELP_HINTS,
+            Some(Popup::Upgrade) => UPGRADE_HINTS,
+            Some(Popup::Reorder) => REORDER_HINTS,
+            None if app.focus == Focus::Details => DETAILS_HINTS,
+            Some(Popup::ConfirmDelete) | Some(Popup::ConfirmLock) => CONFIRM_HINTS,
+            _ => app.bindings(),
+        },
+        InputMode::Editing | InputMode::Inline => EDITING_HINTS,
+        InputMode::Search => SEARCH_HINTS,
+    };
+    let room = usize::from(width.saturating_sub(2));
+    let mut text = String::new();
+    for (key, desc) in hints {
+        let hint = match *key {
+            "I" => format!("I: insert {}", app.insert_position.label()),
+            _ => format!("{}: {}", key, desc),
+        };
+        let sep = if text.is_empty() { "" } else { " | " };
+        if !text.is_empty() && text.width() + sep.len() + hint.width() > room {
+            break;
+        }
+        text.push_str(sep);
+        text.push_str(&hint);
+    }
+    helper_bar(text)
+}
+
+fn helper_bar(text: String) -> Paragraph<'static> {
+    Paragraph::new(Text::raw(text))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .title_alignment(Alignment::Center)
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded),
+        )
+}
+
+/// Centers a `width` x `height` rectangle in `r`, shrinking it to fit.
+fn centered_rect(width: u16, height: u16, r: Rect) -> Rect {
+    let width = width.min(r.width);
+    let height = height.min(r.height);
+    Rect::new(
+        r.x + (r.width - width) / 2,
+        r.y + (r.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Preferred popup width: 70 columns, but never more than 80% of the frame.
+fn popup_width(frame: Rect) -> u16 {
+    let max = (u32::from(frame.width) * 4 / 5) as u16;
+    max.min(70)
+}
+
+/// Number of rows `text` takes once wrapped to `width` columns, at least
+/// one.
+fn wrapped_rows(text: &str, width: u16) -> u16 {
+    text_rows(&Text::raw(text), width).max(1)
+}
+
+/// Number of rows a `Paragraph` wrapped with `Wrap { trim: true }` draws
+/// `text` on in a `width` columns area.
+///
+/// This is a port of the private `WordWrapper` of tui 0.18.0, in
+/// `src/widgets/reflow.rs`, which `Paragraph` wraps with, so the count
+/// matches what is drawn: lines break after the last whitespace that fits,
+/// words wider than the area are cut, whitespace starting a row is dropped
+/// and graphemes wider than the area are skipped. It has to follow any
+/// change to that code on a tui upgrade, which the test comparing it with a
+/// drawn `Paragraph` catches.
+fn text_rows(text: &Text, width: u16) -> u16 {
+    if width == 0 {
+        return 0;
+    }
+    let is_whitespace = |g: &str| g.chars().all(char::is_whitespace) && g != "\u{a0}";
+    let mut rows: u16 = 0;
+    // Graphemes carried over from the previous row when it was broken.
+    let mut next: Vec<&str> = Vec::new();
+    for spans in &text.lines {
+        let mut line = spans.0.iter().flat_map(|span| span.content.graphemes(true));
+        loop {
+            let mut current = std::mem::take(&mut next);
+            let mut current_width: u16 = current.iter().map(|g| g.width() as u16).sum();
+            let mut to_word_end = 0;
+            let mut prev_whitespace = false;
+            let mut broken = false;
+            for g in line.by_ref() {
+                let whitespace = is_whitespace(g);
+                if g.width() as u16 > width || whitespace && current_width == 0 {
+                    continue;
+                }
+                if whitespace && !prev_whitespace {
+                    to_word_end = current.len();
+                }
+                current.push(g);
+                current_width += g.width() as u16;
+                if current_width > width {
+                    let cut = if to_word_end != 0 {
+                        to_word_end
+                    } else {
+                        current.len() - 1
+                    };
+                    next.extend(current[cut..].iter().skip_while(|g| is_whitespace(g)));
+                    broken = true;
+                    break;
+                }
+                prev_whitespace = whitespace;
+            }
+            rows = rows.saturating_add(1);
+            // A broken row leaves the line's end, at least its line break,
+            // for the rows after it.
+            if !broken {
+                break;
+            }
+        }
+    }
+    rows
+}
+
+/// Height of an input popup of the given width, borders included.
+fn input_popup_height(app: &App, popup: Popup, width: u16) -> u16 {
+    let inner_width = width.saturating_sub(2);
+    let rows = |input: &str| text_rows(&input_text(input, app.cursor), inner_width).max(1);
+    let rows = match popup {
+        Popup::NewTaskName
+        | Popup::EditTaskName(_)
+        | Popup::AttachmentPath
+        | Popup::ExportPath
+        | Popup::NewList
+        | Popup::RenameList => rows(&app.input[0]),
+        Popup::NewTaskDue => rows(&app.input[2]),
+        Popup::NewTaskDetails | Popup::EditTaskDetails(_) => rows(&app.input[1]).max(3),
+        Popup::Attachments | Popup::ConfirmDelete | Popup::ConfirmLock => 1,
+        Popup::Help | Popup::Upgrade | Popup::Reorder => 1,
+    };
+    rows.saturating_add(2)
+}
+
+/// Terminal window title, counting the pending tasks of the shown list and
+/// those due on `today`, e.g. `todo-tui — 4 pending (2 due today)`.
+fn window_title(app: &App, today: NaiveDate) -> String {
+    let pending = app.project().list.items.iter().filter(|task| !task.done);
+    let (count, due_today) = pending.fold((0, 0), |(count, due), task| {
+        (count + 1, due + usize::from(task.due == Some(today)))
+    });
+    if due_today == 0 {
+        format!("todo-tui — {} pending", count)
+    } else {
+        format!("todo-tui — {} pending ({} due today)", count, due_today)
+    }
+}
+
+fn list_title(app: &App) -> String {
+    let mut notes = Vec::new();
+    if let Some(filter) = &app.filter {
+        notes.push(format!("filter: {}", sanitize_line(filter)));
+    }
+    if app.sort == SortMode::Priority && app.view == View::Tasks {
+        notes.push(String::from("by priority"));
+    }
+    if app.hide_done && app.view == View::Tasks {
+        let hidden = app
+            .project()
+            .list
+            .items
+            .iter()
+            .filter(|task| task.done)
+            .count();
+        notes.push(format!("{} hidden", hidden));
+    }
+    if app.read_only {
+        notes.push(String::from("read-only"));
+    }
+    if let InputMode::Inline = app.input_mode {
+        notes.push(title_counter(&app.input[0]));
+    }
+    let name = sanitize_line(&app.project().name);
+    let name = match app.view {
+        View::Tasks => name,
+        View::Archive => format!("{}: archive", name),
+    };
+    if notes.is_empty() {
+        name
+    } else {
+        format!("{} ({})", name, notes.join(", "))
+    }
+}
+
+/// Whether the title or details of `task` contain `query`, which must
+/// already be lowercase.
+fn matches_query(task: &Task, query: &str) -> bool {
+    task.msg.to_lowercase().contains(query)
+        || task
+            .details
+            .as_deref()
+            .is_some_and(|details| details.to_lowercase().contains(query))
+}
+
+/// Characters typed out of the maximum title length, e.g. `12/256`.
+fn title_counter(input: &str) -> String {
+    format!("{}/{}", input.chars().count(), MAX_TITLE_LEN)
+}
+
+/// Byte offset in `input` of the cursor at `cursor`, the end for `None`,
+/// kept within `input` and on a character boundary.
+fn cursor_in(input: &str, cursor: Option<usize>) -> usize {
+    let mut at = cursor.map_or(input.len(), |at| at.min(input.len()));
+    while !input.is_char_boundary(at) {
+        at -= 1;
+    }
+    at
+}
+
+/// `input` as typed with the cell under the cursor reversed, as the
+/// terminal cursor cannot follow the popup's word wrapping. A blank cell
+/// under the cursor is a no-break space, which wrapping never trims away.
+fn input_text(input: &str, cursor: Option<usize>) -> Text<'static> {
+    let at = cursor_in(input, cursor);
+    let after = sanitize(&input[at..]);
+    let (under, rest) = match after.chars().next() {
+        Some(c) if !c.is_whitespace() => after.split_at(c.len_utf8()),
+        Some(c) if c != '\n' => ("\u{a0}", &after[c.len_utf8()..]),
+        _ => ("\u{a0}", after.as_str()),
+    };
+    let mut lines: Vec<Spans> = sanitize(&input[..at])
+        .split('\n')
+        .map(|line| Spans::from(line.to_string()))
+        .collect();
+    let mut rest = rest.split('\n');
+    if let Some(line) = lines.last_mut() {
+        line.0.push(Span::styled(
+            under.to_string(),
+            Style::default().add_modifier(Modifier::REVERSED),
+        ));
+        line.0
+            .push(Span::raw(rest.next().unwrap_or_default().to_string()));
+    }
+    lines.extend(rest.map(|line| Spans::from(line.to_string())));
+    Text::from(lines)
+}
+
+fn input_popup(app: &App, popup: Popup) -> Paragraph<'static> {
+    let (text, title) = match popup {
+        Popup::NewTaskName => {
+            let kind = if app.subtask { "subtask" } else { "task" };
+            (
+                input_text(&app.input[0], app.cursor),
+                match &app.input_error {
+                    Some(error) => format!("Add a new {}: {}", kind, sanitize_line(error)),
+                    None => format!("Add a new {} ({})", kind, title_counter(&app.input[0])),
+                },
+            )
+        }
+        Popup::NewTaskDue => (
+            input_text(&app.input[2], app.cursor),
+            match &app.input_error {
+                Some(error) => format!("Due date: {}", sanitize_line(error)),
+                None => String::from("Due date: YYYY-MM-DD, today, tomorrow (blank for none)"),
+            },
+        ),
+        Popup::EditTaskName(_) => (
+            input_text(&app.input[0], app.cursor),
+            format!("Edit task ({})", title_counter(&app.input[0])),
+        ),
+        Popup::NewTaskDetails => (
+            input_text(&app.input[1], app.cursor),
+            String::from("Add details (blank for none)"),
+        ),
+        Popup::EditTaskDetails(_) => (
+            input_text(&app.input[1], app.cursor),
+            String::from("Edit details (blank for none)"),
+        ),
+        Popup::AttachmentPath => (
+            input_text(&app.input[0], app.cursor),
+            String::from("Attach a file (~ expands to home)"),
+        ),
+        Popup::ExportPath => (
+            input_text(&app.input[0], app.cursor),
+            match &app.input_error {
+                Some(error) => format!("Export to: {}", sanitize_line(error)),
+                None => String::from("Export to (~ expands to home)"),
+            },
+        ),
+        Popup::NewList | Popup::RenameList => (
+            input_text(&app.input[0], app.cursor),
+            match (&app.input_error, popup) {
+                (Some(error), _) => format!("List name: {}", sanitize_line(error)),
+                (None, Popup::NewList) => String::from("New list"),
+                _ => String::from("Rename list"),
+            },
+        ),
+        // Drawn by their own functions.
+        Popup::Attachments
+        | Popup::ConfirmDelete
+        | Popup::ConfirmLock
+        | Popup::Help
+        | Popup::Upgrade
+        | Popup::Reorder => (Text::default(), String::new()),
+    };
+    Paragraph::new(text).wrap(Wrap { trim: true }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(title)
+            .title_alignment(Alignment::Center),
+    )
+}
+
+/// Shows the title of the task about to change and asks `question`.
+fn confirm_popup(
+    heading: &'static str,
+    title: String,
+    question: &'static str,
+) -> Paragraph<'static> {
+    let text = Text::from(vec![
+        Spans::from(title),
+        Spans::default(),
+        Spans::from(question),
+    ]);
+    Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(heading)
+                .title_alignment(Alignment::Center),
+        )
+}
+
+/// Lays `bindings` out as keys next to their descriptions, in as many
+/// columns as fit in `width`, read top to bottom then left to right.
+fn help_rows(bindings: &[(&str, &str)], width: u16) -> Vec<String> {
+    let key_width = bindings
+        .iter()
+        .map(|(key, _)| key.width())
+        .max()
+        .unwrap_or(0);
+    let cells: Vec<String> = bindings
+        .iter()
+        .map(|(key, desc)| format!(" {:>width$}  {}", key, desc, width = key_width))
+        .collect();
+    let cell_width = cells.iter().map(|cell| cell.width()).max().unwrap_or(0) + 1;
+    let columns = (usize::from(width) / cell_width).clamp(1, cells.len().max(1));
+    let rows = cells.len().div_ceil(columns);
+    (0..rows)
+        .map(|row| {
+            let mut line = String::new();
+            for cell in cells.iter().skip(row).step_by(rows) {
+                line.push_str(cell);
+                line.push_str(&" ".repeat(cell_width - cell.width()));
+            }
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+/// The help `rows` in a `area` popup, scrolled down by `scroll` rows, which
+/// is first clamped so the last row stays at the bottom of the popup.
+fn help_popup(rows: Vec<String>, area: Rect, scroll: &mut u16) -> Paragraph<'static> {
+    let height = usize::from(area.height.saturating_sub(2));
+    let max = u16::try_from(rows.len().saturating_sub(height)).unwrap_or(u16::MAX);
+    *scroll = (*scroll).min(max);
+    let title = if max > 0 {
+        "Help (j/k to scroll)"
+    } else {
+        "Help"
+    };
+    Paragraph::new(Text::from(
+        rows.into_iter().map(Spans::from).collect::<Vec<_>>(),
+    ))
+    .scroll((*scroll, 0))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(title)
+            .title_alignment(Alignment::Center),
+    )
+}
+
+/// Lists the steps of `plan` in their planned order, each after its
+/// operation, with the number of subtasks going along.
+fn reorder_popup(plan: &Plan) -> List<'static> {
+    let items: Vec<ListItem> = if plan.steps.is_empty() {
+        vec![ListItem::new("No tasks to reorder")]
+    } else {
+        plan.steps
+            .iter()
+            .map(|step| {
+                let mut text = format!("{} {}", step.op.word(), sanitize_line(&step.msg));
+                if step.rows.len() > 1 {
+                    text = format!("{} (+{})", text, step.rows.len() - 1);
+                }
+                let style = match step.op {
+                    Op::Keep => Style::default(),
+                    Op::Done => Style::default().add_modifier(Modifier::DIM),
+                    Op::Drop => Style::default().add_modifier(Modifier::CROSSED_OUT),
+                };
+                ListItem::new(Span::styled(text, style))
+            })
+            .collect()
+    };
+    List::new(items)
+        .highlight_style(Style::default().fg(Color::Black).bg(Color::White))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title("Reorder")
+                .title_alignment(Alignment::Center),
+        )
+}
+
+/// Lists attachment paths as entered, the missing ones in red.
+fn attachments_popup(attachments: &[PathBuf]) -> List<'static> {
+    let items: Vec<ListItem> = if attachments.is_empty() {
+        vec![ListItem::new("No attachments, press a to add one")]
+    } else {
+        attachments
+            .iter()
+            .map(|path| {
+                ListItem::new(attachment_span(
+                    sanitize_line(&path.to_string_lossy()),
+                    path,
+                ))
+            })
+            .collect()
+    };
+    List::new(items)
+        .highlight_style(Style::default().fg(Color::Black).bg(Color::White))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title("Attachments")
+                .title_alignment(Alignment::Center),
+        )
+}
+
+/// `text` standing for the attachment at `path`, red when the file is missing.
+fn attachment_span(text: String, path: &Path) -> Span<'static> {
+    if attachment::exists(path) {
+        Span::raw(text)
+    } else {
+        Span::styled(text, Style::default().fg(Color::Red))
+    }
+}
+
+/// Creation and completion times, due date, attachments and details of
+/// `task` in a `area` pane, scrolled down by `scroll` rows, which is first
+/// clamped so the content's end stays at the bottom of the pane.
+fn details_win(task: &Task, area: Rect, scroll: &mut u16) -> Paragraph<'static> {
+    let width = area.width.saturating_sub(2);
+    let height = area.height.saturating_sub(2);
+    let mut text = Text::default();
+    let today = Local::now().date_naive();
+    let mut times = Vec::new();
+    if let Some(created_at) = task.created_at {
+        times.push(format!(
+            "created {}",
+            days_ago(created_at.date_naive(), today)
+        ));
+    }
+    if let (true, Some(completed_at)) = (task.done, task.completed_at) {
+        times.push(format!(
+            "completed {}",
+            days_ago(completed_at.date_naive(), today)
+        ));
+    }
+    if !times.is_empty() {
+        text.lines.push(Spans::from(times.join(" / ")));
+    }
+    if let Some(due) = task.due {
+        text.lines.push(Spans::from(format!("Due {}", due)));
+    }
+    for path in &task.attachments {
+        let name = path
+            .file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy();
+        text.lines.push(Spans::from(attachment_span(
+            format!("@ {}", sanitize_line(&name)),
+            path,
+        )));
+    }
+    if !text.lines.is_empty() && task.details.is_some() {
+        text.lines.push(Spans::default());
+    }
+    if let Some(details) = &task.details {
+        let needed = height
+            .saturating_add(*scroll)
+            .saturating_sub(text_rows(&text, width));
+        let visible = visible_details(details, width, needed);
+        text.extend(Text::raw(sanitize(visible)));
+    }
+    let rows = text_rows(&text, width);
+    *scroll = (*scroll).min(rows.saturating_sub(height));
+    Paragraph::new(text)
+        .wrap(Wrap { trim: true })
+        .scroll((*scroll, 0))
+}
+
+/// How long before `today` the `day` was, in words.
+fn days_ago(day: NaiveDate, today: NaiveDate) -> String {
+    match (today - day).num_days() {
+        ..=0 => String::from("today"),
+        1 => String::from("yesterday"),
+        days => format!("{} days ago", days),
+    }
+}
+
+/// Leading part of `details` that can show in the first `height` rows of a
+/// `width` columns pane, so huge details are never sanitized and wrapped as
+/// a whole on every frame.
+fn visible_details(details: &str, width: u16, height: u16) -> &str {
+    let max_chars = usize::from(width) * usize::from(height);
+    let mut lines = 0;
+    for (n, (i, c)) in details.char_indices().enumerate() {
+        if n == max_chars {
+            return &details[..i];
+        }
+        if c == '\n' {
+            lines += 1;
+            if lines == height {
+                return &details[..i];
+            }
+        }
+    }
+    details
+}
+
+#[cfg(test)]
+mod tests {
+    use tui::{backend::TestBackend, buffer::Buffer, widgets::Widget};
+
+    use super::*;
+
+    /// Rows `text` takes when drawn word-wrapped in a `width` columns area,
+    /// up to its last non-blank row.
+    fn drawn_rows(text: &str, width: u16) -> u16 {
+        let area = Rect::new(0, 0, width, 200);
+        let mut buf = Buffer::empty(area);
+        Paragraph::new(Text::raw(text.to_string()))
+            .wrap(Wrap { trim: true })
+            .render(area, &mut buf);
+        (0..area.height)
+            .rev()
+            .find(|&y| (0..width).any(|x| buf.get(x, y).symbol != " "))
+            .map_or(0, |y| y + 1)
+    }
+
+    #[test]
+    fn text_rows_follows_word_wrapping() {
+        assert_eq!(text_rows(&Text::raw("abcdef ".repeat(20)), 10), 20);
+        for text in [
+            "a short one",
+            "words of several lengths wrapping over a narrow pane",
+            "averyveryverylongwordthatnevercontainsaspace and more",
+            "spaces   between   words   in   the   middle",
+            "first line\nsecond line that is longer\nx",
+            "wide 漢字漢字漢字漢字 characters",
+        ] {
+            for width in [4, 7, 10, 13] {
+                assert_eq!(
+                    text_rows(&Text::raw(text), width),
+                    drawn_rows(text, width),
+                    "{:?} in {} columns",
+                    text,
+                    width
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn details_scroll_reaches_the_end() {
+        let task = Task::new(String::from("t"), Some("abcdef ".repeat(20)));
+        let area = Rect::new(0, 0, 12, 7);
+        let mut scroll = u16::MAX;
+        details_win(&task, area, &mut scroll);
+        // Two rows of "created today", a blank row and 20 of details, in 5.
+        assert_eq!(scroll, 23 - 5);
+    }
+
+    #[test]
+    fn input_popups_show_the_cursor_of_long_input() {
+        let long = "a long task title ".repeat(12);
+        let popups = [
+            (Popup::NewTaskName, 0),
+            (Popup::EditTaskName(0), 0),
+            (Popup::AttachmentPath, 0),
+            (Popup::NewList, 0),
+            (Popup::RenameList, 0),
+            (Popup::ExportPath, 0),
+            (Popup::NewTaskDue, 2),
+            (Popup::NewTaskDetails, 1),
+        ];
+        for (width, height) in [(80, 24), (200, 60), (40, 12)] {
+            for (popup, field) in popups {
+                let mut app = App::new();
+                app.popup = Some(popup);
+                app.input_mode = InputMode::Editing;
+                app.input[field] = long.clone();
+                let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+                terminal.draw(|f| ui(f, &mut app)).unwrap();
+                let buffer = terminal.backend().buffer();
+                let cursor = buffer
+                    .content()
+                    .iter()
+                    .position(|cell| cell.modifier.contains(Modifier::REVERSED));
+                let frame = Rect::new(0, 0, width, height);
+                let popup_width = popup_width(frame);
+                let area = centered_rect(
+                    popup_width,
+                    input_popup_height(&app, popup, popup_width),
+                    frame,
+                );
+                let Some(cursor) = cursor else {
+                    panic!("no cursor in {:?} at {}x{}", popup, width, height);
+                };
+                let (x, y) = (cursor as u16 % width, cursor as u16 / width);
+                assert!(
+                    area.left() < x
+                        && x < area.right() - 1
+                        && area.top() < y
+                        && y < area.bottom() - 1,
+                    "cursor at {},{} outside {:?} of {:?} at {}x{}",
+                    x,
+                    y,
+                    area,
+                    popup,
+                    width,
+                    height
+                );
+            }
+        }
+    }
+
+    /// Text of the rows of `terminal`'s screen.
+    fn screen(terminal: &Terminal<TestBackend>) -> String {
+        let buffer = terminal.backend().buffer();
+        let width = usize::from(buffer.area.width);
+        let symbols: Vec<&str> = buffer
+            .content()
+            .iter()
+            .map(|cell| &cell.symbol[..])
+            .collect();
+        symbols
+            .chunks(width)
+            .map(|row| row.concat())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn help_fits_80x24() {
+        let mut app = App::new();
+        app.popup = Some(Popup::Help);
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let screen = screen(&terminal);
+        for (key, desc) in NORMAL_HINTS {
+            assert!(
+                screen.contains(&format!("{}  {}", key, desc)),
+                "{} missing",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn help_scrolls_to_its_last_row() {
+        let mut app = App::new();
+        app.popup = Some(Popup::Help);
+        app.help_scroll = u16::MAX;
+        let mut terminal = Terminal::new(TestBackend::new(40, 12)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let (key, desc) = NORMAL_HINTS[NORMAL_HINTS.len() - 1];
+        assert!(screen(&terminal).contains(&format!("{}  {}", key, desc)));
+        assert!(app.help_scroll > 0 && app.help_scroll < u16::MAX);
+    }
+
+    /// An empty directory for `test` to write files in.
+    fn scratch_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("todo-tui-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn export_matches_the_command_line() {
+        let dir = scratch_dir("export");
+        let mut app = App::new();
+        app.path = dir.join("todo.json");
+        let mut task = Task::new(String::from("Ship"), Some(String::from("soon")));
+        task.children.push(Task::new(String::from("Test"), None));
+        app.projects[0].list.items = task::flatten(vec![task]);
+        let mut home = Project::new(String::from("Home"));
+        home.list.items = vec![Task::new(String::from("Water plants"), None)];
+        app.projects.push(home);
+        app.save().unwrap();
+
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL),
+        );
+        assert_eq!(app.input[0], dir.join("todo.md").display().to_string());
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        let status = format!("Exported to {}", dir.join("todo.md").display());
+        assert_eq!(app.status, Some(status));
+        let from_app = fs::read_to_string(dir.join("todo.md")).unwrap();
+        crate::cli::export(&app.path, &dir.join("cli.md")).unwrap();
+        let from_cli = fs::read_to_string(dir.join("cli.md")).unwrap();
+        assert_eq!(from_app, from_cli);
+        assert!(from_app.contains("# Home\n\n- [ ] Water plants\n"));
+
+        app.ask_export_path();
+        app.input[0] = dir.join("missing").join("todo.md").display().to_string();
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(matches!(app.popup, Some(Popup::ExportPath)));
+        assert!(app.input_error.is_some());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// Tasks named after `names`, a name ending in `x` being done, one
+    /// starting with `!` locked and one starting with `-` a subtask of the
+    /// task above it.
+    fn tasks(names: &[&str]) -> Vec<Task> {
+        names
+            .iter()
+            .map(|name| {
+                let subtask = name.starts_with('-');
+                let name = name.trim_start_matches('-');
+                let mut task = Task::new(name.trim_start_matches('!').to_string(), None);
+                task.done = name.ends_with('x');
+                task.locked = name.starts_with('!');
+                task.subtask = subtask;
+                task
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clear_done_reports_skipped_locked_tasks() {
+        let mut app = App::new();
+        app.projects[0].list.items = tasks(&["ax", "!bx", "c", "!dx"]);
+        normal_key(&mut app, KeyCode::Char('D'));
+        assert_eq!(
+            app.status.as_deref(),
+            Some("Cleared 1 done task, skipped 2 locked tasks")
+        );
+        assert_eq!(app.projects[0].list.items.len(), 3);
+        normal_key(&mut app, KeyCode::Char('D'));
+        assert_eq!(
+            app.status.as_deref(),
+            Some("Nothing cleared, 2 locked tasks skipped")
+        );
+    }
+
+    #[test]
+    fn toggle_all_reports_skipped_locked_tasks() {
+        let mut app = App::new();
+        app.projects[0].list.items = tasks(&["a", "!b", "cx"]);
+        normal_key(&mut app, KeyCode::Char('T'));
+        assert_eq!(
+            app.status.as_deref(),
+            Some("Marked 1 task done, skipped 1 locked task")
+        );
+        normal_key(&mut app, KeyCode::Char('T'));
+        assert_eq!(app.status.as_deref(), Some("Marked 2 tasks pending"));
+        app.projects[0].list.items = tasks(&["!ax"]);
+        normal_key(&mut app, KeyCode::Char('T'));
+        assert_eq!(
+            app.status.as_deref(),
+            Some("Nothing marked pending, 1 locked task skipped")
+        );
+    }
+
+    #[test]
+    fn toggle_all_keeps_a_task_with_a_locked_pending_subtask_pending() {
+        let mut app = App::new();
+        app.projects[0].list.items = tasks(&["a", "-!b", "-c", "d"]);
+        normal_key(&mut app, KeyCode::Char('T'));
+        let done: Vec<_> = app.projects[0].list.items.iter().map(|t| t.done).collect();
+        assert_eq!(done, [false, false, true, true]);
+    }
+
+    #[test]
+    fn adding_a_subtask_reopens_its_done_task() {
+        let mut app = App::new();
+        app.projects[0].list.items = tasks(&["ax", "-bx"]);
+        app.projects[0].list.state.select(Some(1));
+        normal_key(&mut app, KeyCode::Char('o'));
+        app.input[0] = String::from("c");
+        for _ in 0..3 {
+            handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        }
+        let rows = &app.projects[0].list.items;
+        assert!(rows[2].subtask && !rows[2].done);
+        assert!(!rows[0].done);
+        app.undo(false);
+        let rows = &app.projects[0].list.items;
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].done);
+
+        app.projects[0].list.items[0].locked = true;
+        normal_key(&mut app, KeyCode::Char('o'));
+        assert!(app.popup.is_none());
+        assert_eq!(app.status.as_deref(), Some("Its task is done and locked"));
+    }
+
+    #[test]
+    fn pending_subtask_reopens_its_done_task() {
+        let mut app = App::new();
+        app.projects[0].list.items = tasks(&["ax", "-bx"]);
+        app.projects[0].list.state.select(Some(1));
+        normal_key(&mut app, KeyCode::Enter);
+        assert!(!app.projects[0].list.items[0].done);
+        app.undo(false);
+        assert!(app.projects[0].list.items[0].done);
+
+        normal_key(&mut app, KeyCode::Enter);
+        normal_key(&mut app, KeyCode::Char('k'));
+        normal_key(&mut app, KeyCode::Enter);
+        assert_eq!(app.status.as_deref(), Some("Finish its subtasks first"));
+        app.projects[0].list.items = tasks(&["!ax", "-bx"]);
+        normal_key(&mut app, KeyCode::Char('j'));
+        normal_key(&mut app, KeyCode::Enter);
+        assert_eq!(app.status.as_deref(), Some("Its task is locked"));
+    }
+
+    #[test]
+    fn select_edges_of_the_shown_rows() {
+        let mut app = App::new();
+        app.projects[0].list.items = tasks(&["ax", "b", "c", "dx"]);
+        app.hide_done = true;
+        app.select_edge(true);
+        assert_eq!(app.shown().state.selected(), Some(2));
+        app.select_edge(false);
+        assert_eq!(app.shown().state.selected(), Some(1));
+
+        app.projects[0].list.items.clear();
+        app.projects[0].list.state.select(None);
+        app.select_edge(true);
+        assert_eq!(app.shown().state.selected(), None);
+    }
+
+    #[test]
+    fn lone_g_selects_the_first_row_once_it_times_out() {
+        let mut app = App::new();
+        app.projects[0].list.items = tasks(&["a", "b", "c"]);
+        app.projects[0].list.state.select(Some(2));
+        let Some(since) = Instant::now().checked_sub(SEQUENCE_TIMEOUT) else {
+            return;
+        };
+        app.pending = Some(('g', since));
+        expire_pending(&mut app);
+        assert_eq!(app.pending, None);
+        assert_eq!(app.shown().state.selected(), Some(0));
+    }
+
+    #[test]
+    fn a_burst_of_j_moves_once_and_clears_the_status() {
+        let mut app = App::new();
+        app.projects[0].list.items = tasks(&["a", "b", "c", "d"]);
+        app.projects[0].list.state.select(Some(0));
+        app.status = Some(String::from("Deleted, u to undo"));
+        let mut events = Events::channel();
+        let worker = events.worker();
+        let j = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        for _ in 0..2 {
+            assert!(worker.post(AppEvent::Input(j)));
+        }
+        assert!(!handle_input(&mut app, &mut events, j));
+        assert_eq!(app.shown().state.selected(), Some(3));
+        assert_eq!(app.status, None);
+    }
+
+    #[test]
+    fn ends_wrap_on_a_press_but_not_while_held() {
+        let mut app = App::new();
+        app.projects[0].list.items = tasks(&["a", "b", "c"]);
+        app.projects[0].list.state.select(Some(0));
+        app.navigate(false, 1, true);
+        assert_eq!(app.shown().state.selected(), Some(0));
+        app.last_nav = None;
+        app.navigate(false, 1, false);
+        assert_eq!(app.shown().state.selected(), Some(2));
+        app.last_nav = None;
+        app.navigate(true, 1, true);
+        assert_eq!(app.shown().state.selected(), Some(2));
+        app.last_nav = None;
+        app.navigate(true, 1, false);
+        assert_eq!(app.shown().state.selected(), Some(0));
+
+        app.wrap_when_held = true;
+        app.navigate(false, 2, true);
+        assert_eq!(app.shown().state.selected(), Some(1));
+    }
+
+    #[test]
+    fn saving_large_details_warns() {
+        let mut app = App::new();
+        app.projects[0].list.items = tasks(&["a"]);
+        app.projects[0].list.state.select(Some(0));
+        app.popup = Some(Popup::EditTaskDetails(0));
+        app.input_mode = InputMode::Editing;
+        app.input[1] = "x".repeat(view::LARGE_DETAILS + 1);
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(
+            app.status.as_deref(),
+            Some("Saved 64.0 KiB of details, large details bloat the task file")
+        );
+        assert_eq!(
+            app.projects[0].list.items[0]
+                .details
+                .as_ref()
+                .map(String::len),
+            Some(view::LARGE_DETAILS + 1)
+        );
+    }
+
+    #[test]
+    fn read_only_refuses_edits() {
+        let mut app = App::new();
+        app.read_only = true;
+        app.projects[0].list.items = tasks(&["a", "b"]);
+        app.projects[0].list.state.select(Some(0));
+        for code in [KeyCode::Char('n'), KeyCode::Char('d'), KeyCode::Enter] {
+            handle_key(&mut app, KeyEvent::new(code, KeyModifiers::NONE));
+            assert!(app.popup.is_none());
+            assert!(app
+                .status
+                .as_deref()
+                .is_some_and(|s| s.starts_with("Read-only")));
+        }
+        assert!(!app.projects[0].list.items[0].done);
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE),
+        );
+        assert_eq!(app.status, None);
+        assert_eq!(app.shown().state.selected(), Some(1));
+    }
+
+    fn list(items: &[char], selected: Option<usize>) -> StateFullList<char> {
+        let mut list = StateFullList {
+            state: TaskListState::default(),
+            items: items.to_vec(),
+        };
+        list.state.select(selected);
+        list
+    }
+
+    #[test]
+    fn remove_keeps_the_selection_at_the_same_index() {
+        for (index, left, selected) in [
+            (0, vec!['b', 'c'], Some(0)),
+            (1, vec!['a', 'c'], Some(1)),
+            (2, vec!['a', 'b'], Some(1)),
+        ] {
+            let mut list = list(&['a', 'b', 'c'], Some(index));
+            assert_eq!(list.remove(index), ['a', 'b', 'c'][index]);
+            assert_eq!(list.items, left);
+            assert_eq!(list.state.selected(), selected);
+        }
+        let mut only = list(&['a'], Some(0));
+        assert_eq!(only.remove(0), 'a');
+        assert_eq!(only.state.selected(), None);
+    }
+
+    #[test]
+    fn remove_selected_removes_the_selected_item() {
+        for (selected, left, after) in [
+            (0, vec!['b', 'c'], Some(0)),
+            (1, vec!['a', 'c'], Some(1)),
+            (2, vec!['a', 'b'], Some(1)),
+        ] {
+            let mut list = list(&['a', 'b', 'c'], Some(selected));
+            assert_eq!(list.remove_selected(), Some(['a', 'b', 'c'][selected]));
+            assert_eq!(list.items, left);
+            assert_eq!(list.state.selected(), after);
+        }
+        let mut only = list(&['a'], Some(0));
+        assert_eq!(only.remove_selected(), Some('a'));
+        assert!(only.items.is_empty());
+        assert_eq!(only.state.selected(), None);
+        assert_eq!(only.remove_selected(), None);
+        let mut unselected = list(&['a'], None);
+        assert_eq!(unselected.remove_selected(), None);
+        assert_eq!(unselected.items, vec!['a']);
+    }
+
+    /// App typing `input` as a new task name, the cursor at its end.
+    fn typing(input: &str) -> App {
+        let mut app = App::new();
+        app.popup = Some(Popup::NewTaskName);
+        app.input_mode = InputMode::Editing;
+        app.input[0] = input.to_string();
+        app
+    }
+
+    #[test]
+    fn blank_title_is_refused() {
+        let mut app = typing("  ");
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(matches!(app.popup, Some(Popup::NewTaskName)));
+        assert_eq!(app.input_error.as_deref(), Some("the title is empty"));
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE),
+        );
+        assert_eq!(app.input_error, None);
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(matches!(app.popup, Some(Popup::NewTaskDetails)));
+    }
+
+    #[test]
+    fn backspace_removes_a_whole_zwj_sequence() {
+        let mut app = typing("a\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}");
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE),
+        );
+        assert_eq!(app.input[0], "a");
+    }
+
+    #[test]
+    fn backspace_removes_a_whole_combining_sequence() {
+        let mut app = typing("cafe\u{301}\u{323}");
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE),
+        );
+        assert_eq!(app.input[0], "caf");
+
+        let mut app = typing("ne\u{301}e");
+        handle_key(&mut app, KeyEvent::new(KeyCode::Left, KeyModifiers::NONE));
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE),
+        );
+        assert_eq!(app.input[0], "ne");
+    }
+
+    #[test]
+    fn insert_position_index_per_mode() {
+        let mut parent = Task::new(String::from("a"), None);
+        parent.children = tasks(&["a1", "a2"]);
+        // a, a1, a2, b
+        let rows = task::flatten(vec![parent, Task::new(String::from("b"), None)]);
+
+        let below = InsertPosition::BelowSelection;
+        assert_eq!(below.index(&rows, Some(0)), 3, "after the subtasks");
+        assert_eq!(below.index(&rows, Some(1)), 3, "after the subtask's family");
+        assert_eq!(below.index(&rows, Some(3)), 4);
+        assert_eq!(below.index(&rows, None), 4);
+        assert_eq!(below.index(&rows, Some(9)), 4, "stale selection");
+        assert_eq!(below.index(&[], None), 0);
+
+        for selected in [None, Some(0), Some(3)] {
+            assert_eq!(InsertPosition::Top.index(&rows, selected), 0);
+            assert_eq!(InsertPosition::End.index(&rows, selected), 4);
+        }
+        assert_eq!(InsertPosition::End.index(&[], None), 0);
+    }
+
+    #[test]
+    fn random_pick_never_repeats_and_skips_done_tasks() {
+        let mut app = App::new();
+        app.projects[0].list.items = tasks(&["a", "bx", "c", "d"]);
+        app.rng = Rng::new(3);
+        let mut picked = [0; 4];
+        for _ in 0..300 {
+            let last = app.last_pick;
+            app.pick_random();
+            let pick = app.last_pick.unwrap();
+            assert_ne!(Some(pick), last);
+            assert_eq!(app.shown().state.selected(), Some(pick));
+            picked[pick] += 1;
+        }
+        assert_eq!(picked[1], 0);
+        assert!(picked.iter().enumerate().all(|(i, &n)| i == 1 || n > 50));
+
+        // A single pending task is picked again rather than nothing.
+        app.projects[0].list.items = tasks(&["a"]);
+        app.pick_random();
+        app.pick_random();
+        assert_eq!(app.last_pick, Some(0));
+    }
+
+    #[test]
+    fn unchanged_generation_skips_the_draw() {
+        let mut app = App::new();
+        let mut redraw = Redraw::default();
+        assert!(redraw.needed(app.generation));
+        redraw.done(app.generation);
+        assert!(!redraw.needed(app.generation));
+        redraw.keepalive(Power { low: true });
+        redraw.keepalive(Power::default());
+        assert!(!redraw.needed(app.generation), "keepalive not due yet");
+        app.touch();
+        assert!(redraw.needed(app.generation));
+    }
+
+    #[test]
+    fn keepalive_forces_a_draw_outside_low_power() {
+        let mut redraw = Redraw::default();
+        redraw.done(0);
+        redraw.last = Instant::now().checked_sub(Duration::from_secs(60));
+        redraw.keepalive(Power { low: true });
+        assert!(!redraw.needed(0));
+        redraw.keepalive(Power::default());
+        assert!(redraw.needed(0));
+    }
+
+    #[test]
+    fn resize_forces_a_draw() {
+        let app = App::new();
+        let mut redraw = Redraw::default();
+        redraw.done(app.generation);
+        redraw.force();
+        assert!(redraw.needed(app.generation));
+    }
+}
@@ -0,0 +1,244 @@
+//! Bulk changes to a list, `git rebase -i` style: a plan is edited over a
+//! snapshot of the list and nothing changes until it is applied as a whole.
+
+use std::ops::Range;
+
+use crate::task::{self, Task};
+
+/// What applying a plan does to a task and its subtasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Keep,
+    Done,
+    Drop,
+}
+
+impl Op {
+    pub fn word(self) -> &'static str {
+        match self {
+            Op::Keep => "keep",
+            Op::Done => "done",
+            Op::Drop => "drop",
+        }
+    }
+}
+
+/// A task of the snapshot, with its subtasks, and what to do with it.
+#[derive(Debug, Clone)]
+pub struct Step {
+    /// Rows of the task in the snapshot.
+    pub rows: Range<usize>,
+    pub op: Op,
+    pub msg: String,
+    pub locked: bool,
+}
+
+/// Counts of what applying a plan changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub moved: usize,
+    pub done: usize,
+    pub dropped: usize,
+}
+
+impl Summary {
+    pub fn is_empty(self) -> bool {
+        self == Summary::default()
+    }
+}
+
+/// Order and operations planned for the tasks of a list, one step per task
+/// in the order they will end up in.
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub steps: Vec<Step>,
+    pub selected: usize,
+    /// Title and subtask flag of each row the plan was made from.
+    snapshot: Vec<(String, bool)>,
+}
+
+impl Plan {
+    /// Plan keeping the flattened `rows` as they are.
+    pub fn new(rows: &[Task]) -> Self {
+        let mut steps = Vec::new();
+        let mut start = 0;
+        while start < rows.len() {
+            let family = task::family(rows, start);
+            steps.push(Step {
+                op: Op::Keep,
+                msg: rows[start].msg.clone(),
+                locked: rows[family.clone()].iter().any(|row| row.locked),
+                rows: family.clone(),
+            });
+            start = family.end;
+        }
+        Plan {
+            steps,
+            selected: 0,
+            snapshot: rows
+                .iter()
+                .map(|row| (row.msg.clone(), row.subtask))
+                .collect(),
+        }
+    }
+
+    /// Moves the selected step one place up or down, along with the
+    /// selection.
+    pub fn move_selected(&mut self, up: bool) {
+        let k = self.selected;
+        let to = if up { k.checked_sub(1) } else { Some(k + 1) };
+        if let Some(to) = to.filter(|&to| to < self.steps.len()) {
+            self.steps.swap(k, to);
+            self.selected = to;
+        }
+    }
+
+    /// Gives the selected step `op`, or back `Op::Keep` if it has it
+    /// already. Returns false, changing nothing, for a locked task.
+    pub fn toggle(&mut self, op: Op) -> bool {
+        let Some(step) = self.steps.get_mut(self.selected) else {
+            return true;
+        };
+        if step.locked {
+            return false;
+        }
+        step.op = if step.op == op { Op::Keep } else { op };
+        true
+    }
+
+    /// The rows the plan turns `rows` into, and what changed. `None` if
+    /// `rows` are no longer the ones the plan was made from.
+    pub fn apply(&self, rows: &[Task]) -> Option<(Vec<Task>, Summary)> {
+        let unchanged = rows.len() == self.snapshot.len()
+            && rows
+                .iter()
+                .zip(&self.snapshot)
+                .all(|(row, (msg, subtask))| row.msg == *msg && row.subtask == *subtask);
+        if !unchanged {
+            return None;
+        }
+        let mut summary = Summary::default();
+        let mut kept = Vec::with_capacity(self.steps.len());
+        for step in &self.steps {
+            if step.op == Op::Drop {
+                summary.dropped += 1;
+                continue;
+            }
+            let mut family = rows[step.rows.clone()].to_vec();
+            if step.op == Op::Done && family.iter().any(|row| !row.done) {
+                family.iter_mut().for_each(|row| row.set_done(true));
+                summary.done += 1;
+            }
+            kept.push((step.rows.start, family));
+        }
+        // A kept task moved if it is not where it was among the kept ones.
+        let mut remaining: Vec<usize> = kept.iter().map(|(start, _)| *start).collect();
+        remaining.sort_unstable();
+        summary.moved = kept
+            .iter()
+            .zip(&remaining)
+            .filter(|((start, _), original)| start != *original)
+            .count();
+        Some((
+            kept.into_iter().flat_map(|(_, family)| family).collect(),
+            summary,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Flattened rows of tasks `a` (with subtask `a1`), `b` and `c`.
+    fn rows() -> Vec<Task> {
+        let mut a = Task::new(String::from("a"), None);
+        a.children.push(Task::new(String::from("a1"), None));
+        task::flatten(vec![
+            a,
+            Task::new(String::from("b"), None),
+            Task::new(String::from("c"), None),
+        ])
+    }
+
+    fn titles(rows: &[Task]) -> Vec<(&str, bool)> {
+        rows.iter()
+            .map(|row| (row.msg.as_str(), row.done))
+            .collect()
+    }
+
+    #[test]
+    fn new_plan_keeps_every_task_with_its_subtasks() {
+        let plan = Plan::new(&rows());
+        let steps: Vec<_> = plan
+            .steps
+            .iter()
+            .map(|step| (step.msg.as_str(), step.rows.clone(), step.op))
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                ("a", 0..2, Op::Keep),
+                ("b", 2..3, Op::Keep),
+                ("c", 3..4, Op::Keep)
+            ]
+        );
+        let (kept, summary) = plan.apply(&rows()).unwrap();
+        assert_eq!(titles(&kept), titles(&rows()));
+        assert!(summary.is_empty());
+    }
+
+    #[test]
+    fn apply_moves_marks_and_drops() {
+        let rows = rows();
+        let mut plan = Plan::new(&rows);
+        plan.selected = 2;
+        plan.move_selected(true);
+        plan.move_selected(true);
+        plan.move_selected(true);
+        assert_eq!(plan.selected, 0);
+        plan.selected = 1;
+        assert!(plan.toggle(Op::Done));
+        plan.selected = 2;
+        assert!(plan.toggle(Op::Drop));
+
+        let (kept, summary) = plan.apply(&rows).unwrap();
+        assert_eq!(titles(&kept), [("c", false), ("a", true), ("a1", true)]);
+        assert_eq!(
+            summary,
+            Summary {
+                moved: 2,
+                done: 1,
+                dropped: 1
+            }
+        );
+    }
+
+    #[test]
+    fn toggle_switches_back_to_keep_and_refuses_locked_tasks() {
+        let mut rows = rows();
+        rows[1].locked = true;
+        let mut plan = Plan::new(&rows);
+        assert!(!plan.toggle(Op::Drop));
+        assert_eq!(plan.steps[0].op, Op::Keep);
+        plan.selected = 1;
+        assert!(plan.toggle(Op::Drop));
+        assert!(plan.toggle(Op::Drop));
+        assert_eq!(plan.steps[1].op, Op::Keep);
+    }
+
+    #[test]
+    fn apply_refuses_a_changed_list() {
+        let plan = Plan::new(&rows());
+        let mut renamed = rows();
+        renamed[2].msg = String::from("B");
+        assert!(plan.apply(&renamed).is_none());
+        let mut shorter = rows();
+        shorter.pop();
+        assert!(plan.apply(&shorter).is_none());
+        // Marking a task done elsewhere does not invalidate the plan.
+        let mut done = rows();
+        done[3].set_done(true);
+        assert!(plan.apply(&done).is_some());
+    }
+}
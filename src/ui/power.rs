@@ -0,0 +1,63 @@
+//! How often the app wakes up on its own. Low power mode stretches every
+//! timer so an idle app costs next to no CPU.
+
+use std::time::Duration;
+
+/// Interval between two ticks of the event loop.
+const TICK_RATE: Duration = Duration::from_millis(250);
+
+/// Interval between two ticks in low power mode.
+const LOW_POWER_TICK_RATE: Duration = Duration::from_secs(5);
+
+/// Longest time the screen goes without a redraw, even when nothing changed.
+const KEEPALIVE: Duration = Duration::from_secs(1);
+
+/// Timers of the event loop for the current power mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Power {
+    pub low: bool,
+}
+
+impl Power {
+    pub fn tick_rate(self) -> Duration {
+        if self.low {
+            LOW_POWER_TICK_RATE
+        } else {
+            TICK_RATE
+        }
+    }
+
+    /// Longest time the screen goes without a redraw, `None` when it is
+    /// only redrawn on changes.
+    pub fn keepalive(self) -> Option<Duration> {
+        (!self.low).then_some(KEEPALIVE)
+    }
+
+    /// Whether changes are saved as soon as they are made, rather than
+    /// together on the next tick.
+    pub fn save_at_once(self) -> bool {
+        !self.low
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normal_mode_ticks_often_and_keeps_the_screen_alive() {
+        let power = Power::default();
+        assert_eq!(power.tick_rate(), TICK_RATE);
+        assert_eq!(power.keepalive(), Some(KEEPALIVE));
+        assert!(power.save_at_once());
+    }
+
+    #[test]
+    fn low_power_mode_stretches_every_timer() {
+        let power = Power { low: true };
+        assert_eq!(power.tick_rate(), LOW_POWER_TICK_RATE);
+        assert!(power.tick_rate() > Power::default().tick_rate());
+        assert_eq!(power.keepalive(), None);
+        assert!(!power.save_at_once());
+    }
+}
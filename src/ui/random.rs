@@ -0,0 +1,78 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Small xorshift generator, good enough to pick tasks and seedable so a
+/// sequence of picks can be replayed.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift never leaves zero, so that seed is replaced.
+        Rng {
+            state: if seed == 0 {
+                0x9e37_79b9_7f4a_7c15
+            } else {
+                seed
+            },
+        }
+    }
+
+    /// Seeded from the clock.
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        Rng::new(nanos)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    /// Uniform index in `0..n`, `n` being non-zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        // Rejection sampling keeps the draw free of modulo bias.
+        let n = n as u64;
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return (x % n) as usize;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_draws() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let draws: Vec<usize> = (0..100).map(|_| a.below(1000)).collect();
+        assert_eq!(draws, (0..100).map(|_| b.below(1000)).collect::<Vec<_>>());
+        assert_ne!(Rng::new(0).next_u64(), 0);
+    }
+
+    #[test]
+    fn below_is_uniform() {
+        let mut rng = Rng::new(7);
+        let mut counts = [0usize; 6];
+        for _ in 0..60_000 {
+            counts[rng.below(6)] += 1;
+        }
+        for count in counts {
+            assert!((9_000..11_000).contains(&count), "{:?}", counts);
+        }
+        assert!((0..1_000).all(|_| rng.below(1) == 0));
+    }
+}
@@ -0,0 +1,108 @@
+/// Makes user supplied text safe to hand to the terminal.
+///
+/// ANSI escape sequences and control characters are dropped, tabs become a
+/// space and runs of zero-width characters are collapsed to a single one, so
+/// pasted content cannot move the cursor, recolor the screen or stall the
+/// width computations. Newlines are kept.
+pub fn sanitize(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut zero_width_run = false;
+    while let Some(c) = chars.next() {
+        if is_zero_width(c) {
+            if !zero_width_run {
+                out.push(c);
+            }
+            zero_width_run = true;
+            continue;
+        }
+        zero_width_run = false;
+        match c {
+            // Any other escape is dropped alone, keeping what follows it.
+            '\u{1b}' => match chars.peek() {
+                // CSI: parameters up to a final byte in '@'..='~'.
+                Some('[') => {
+                    chars.next();
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC: terminated by BEL or ST (ESC \).
+                Some(']') => {
+                    chars.next();
+                    while let Some(c) = chars.next() {
+                        if c == '\u{7}' {
+                            break;
+                        }
+                        if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\n' => out.push('\n'),
+            '\t' => out.push(' '),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Same as [`sanitize`] for text shown on a single row: newlines become spaces.
+pub fn sanitize_line(text: &str) -> String {
+    sanitize(text).replace('\n', " ")
+}
+
+fn is_zero_width(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{2060}' | '\u{feff}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_csi_sequences() {
+        assert_eq!(sanitize("a\u{1b}[31mred\u{1b}[0m b"), "ared b");
+        assert_eq!(sanitize("\u{1b}[2J\u{1b}[1;1Hclear"), "clear");
+        // Unterminated at the end of the text.
+        assert_eq!(sanitize("x\u{1b}[12"), "x");
+    }
+
+    #[test]
+    fn drops_osc_sequences() {
+        assert_eq!(sanitize("\u{1b}]0;title\u{7}text"), "text");
+        assert_eq!(sanitize("\u{1b}]8;;http://x\u{1b}\\link"), "link");
+    }
+
+    #[test]
+    fn drops_a_lone_escape_only() {
+        assert_eq!(sanitize("a\u{1b}bc"), "abc");
+        assert_eq!(sanitize("a\u{1b}"), "a");
+    }
+
+    #[test]
+    fn drops_c0_and_c1_controls() {
+        assert_eq!(sanitize("a\u{0}b\u{7}c\u{8}d\re"), "abcde");
+        assert_eq!(sanitize("a\u{85}b\u{9b}c"), "abc");
+        assert_eq!(sanitize("a\tb\nc"), "a b\nc");
+        assert_eq!(sanitize_line("a\nb"), "a b");
+    }
+
+    #[test]
+    fn collapses_zero_width_floods_and_keeps_zwj_sequences() {
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        assert_eq!(sanitize(family), family);
+        let flood = format!("a{}b", "\u{200d}".repeat(10_000));
+        assert_eq!(sanitize(&flood), "a\u{200d}b");
+        assert_eq!(sanitize("a\u{200b}\u{feff}\u{2060}b"), "a\u{200b}b");
+    }
+}
@@ -0,0 +1,61 @@
+//! Terminal window title: saved on start, updated while running and put
+//! back on exit, even when the app panics.
+
+use std::io::{self, Write};
+
+use crossterm::{queue, terminal::SetTitle};
+
+/// Saves the terminal window title on the terminal's stack (XTWINOPS).
+const PUSH_TITLE: &str = "\x1b[22;0t";
+
+/// Restores the window title saved by [`PUSH_TITLE`].
+const POP_TITLE: &str = "\x1b[23;0t";
+
+/// Saves the current title so [`restore`] can put it back.
+pub fn save(out: &mut impl Write) -> io::Result<()> {
+    write!(out, "{}", PUSH_TITLE)?;
+    out.flush()
+}
+
+/// Shows `title` as the window title.
+pub fn set(out: &mut impl Write, title: &str) -> io::Result<()> {
+    queue!(out, SetTitle(title))?;
+    out.flush()
+}
+
+/// Puts back the title saved by [`save`]. The title is cleared first, so
+/// a terminal without a title stack is not left showing ours.
+pub fn restore(out: &mut impl Write) -> io::Result<()> {
+    queue!(out, SetTitle(""))?;
+    write!(out, "{}", POP_TITLE)?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(f: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> String {
+        let mut out = Vec::new();
+        f(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn save_pushes_the_title() {
+        assert_eq!(written(save), PUSH_TITLE);
+    }
+
+    #[test]
+    fn set_writes_the_title() {
+        assert_eq!(
+            written(|out| set(out, "todo-tui — 2 pending")),
+            "\x1b]0;todo-tui — 2 pending\x07"
+        );
+    }
+
+    #[test]
+    fn restore_clears_the_title_then_pops_it() {
+        assert_eq!(written(restore), format!("\x1b]0;\x07{}", POP_TITLE));
+    }
+}
@@ -0,0 +1,447 @@
+use std::ops::Range;
+
+use chrono::{Local, NaiveDate};
+use tui::{
+    style::Style,
+    text::{Span, Spans},
+    widgets::ListItem,
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::{
+    sanitize::sanitize_line,
+    widget::{RowFormat, Theme},
+};
+use crate::task::{Priority, Task};
+
+/// Details longer than this get a size badge on their row.
+pub(super) const LARGE_DETAILS: usize = 64 * 1024;
+
+/// Mark of a locked task, before its title.
+const LOCK: &str = "🔒 ";
+
+/// How the number gutter in front of each row is filled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NumberMode {
+    #[default]
+    None,
+    Absolute,
+    Relative,
+}
+
+impl NumberMode {
+    pub fn cycle(self) -> Self {
+        match self {
+            NumberMode::None => NumberMode::Absolute,
+            NumberMode::Absolute => NumberMode::Relative,
+            NumberMode::Relative => NumberMode::None,
+        }
+    }
+
+    /// Number shown for the row at `index`, vim style: relative numbers
+    /// count the distance to the selection, which keeps its own index.
+    fn number(self, index: usize, selected: Option<usize>) -> Option<usize> {
+        match self {
+            NumberMode::None => None,
+            NumberMode::Absolute => Some(index + 1),
+            NumberMode::Relative => match selected {
+                Some(s) if s != index => Some(s.abs_diff(index)),
+                _ => Some(index + 1),
+            },
+        }
+    }
+}
+
+/// Symbols marking done and pending tasks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Checkbox {
+    /// `✓` on done tasks, nothing on pending ones.
+    #[default]
+    Check,
+    /// `[x]` and `[ ]`, for terminals that do not render `✓`.
+    Brackets,
+}
+
+impl Checkbox {
+    /// Mark of a done or pending task, padded to the columns of the widest
+    /// so titles start at the same column either way.
+    fn mark(self, done: bool) -> String {
+        let (done_mark, pending_mark) = match self {
+            Checkbox::Check => ("✓", ""),
+            Checkbox::Brackets => ("[x]", "[ ]"),
+        };
+        let width = done_mark.width().max(pending_mark.width());
+        let mark = if done { done_mark } else { pending_mark };
+        format!("{}{}", mark, " ".repeat(width - mark.width()))
+    }
+}
+
+/// What a row needs to know about the list it is displayed in.
+#[derive(Debug, Clone, Copy)]
+pub struct RowContext<'a> {
+    pub selected: Option<usize>,
+    pub numbers: NumberMode,
+    pub checkbox: Checkbox,
+    pub gutter_width: usize,
+    pub theme: &'a Theme,
+    /// Date against which due dates are compared.
+    pub today: NaiveDate,
+    /// Input replacing the selected row's title while it is edited inline,
+    /// with the byte offset of its cursor.
+    pub editing: Option<(&'a str, usize)>,
+    /// Lowercase filter whose match is marked in each title.
+    pub query: Option<&'a str>,
+    /// Columns available to a row.
+    pub width: usize,
+    /// Whether a column is kept for the lock on every row, so titles stay
+    /// aligned when some rows are locked.
+    pub lock_column: bool,
+}
+
+impl<'a> RowContext<'a> {
+    pub fn new(
+        selected: Option<usize>,
+        len: usize,
+        format: RowFormat,
+        theme: &'a Theme,
+        editing: Option<(&'a str, usize)>,
+        query: Option<&'a str>,
+        width: usize,
+    ) -> Self {
+        let gutter_width = match format.numbers {
+            NumberMode::None => 0,
+            _ => len.max(1).to_string().len(),
+        };
+        RowContext {
+            selected,
+            numbers: format.numbers,
+            checkbox: format.checkbox,
+            gutter_width,
+            theme,
+            today: Local::now().date_naive(),
+            editing,
+            query,
+            width,
+            lock_column: false,
+        }
+    }
+}
+
+/// Display-ready representation of one row of the task list.
+///
+/// Every formatting decision about a `Task` is taken here, so the drawing
+/// code only places already built views on screen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskView {
+    /// Gutter, done mark and lock, drawn before the marker.
+    pub prefix: String,
+    /// Priority marker and its own style.
+    pub marker: Option<(&'static str, Style)>,
+    /// Title or edited input, after the marker.
+    pub text: String,
+    pub style: Style,
+    /// Bytes of the text matching the filter, with their style.
+    pub matched: Option<(Range<usize>, Style)>,
+    /// Column of the text cursor, set on the row being edited inline.
+    pub cursor: Option<u16>,
+}
+
+impl TaskView {
+    /// View of `task`, shown as row `index` of the list.
+    pub fn new(task: &Task, index: usize, ctx: &RowContext) -> Self {
+        let gutter = match ctx.numbers.number(index, ctx.selected) {
+            Some(n) => format!("{:>width$} ", n, width = ctx.gutter_width),
+            None => String::new(),
+        };
+        let indent = if task.subtask { "  " } else { "" };
+        let mark = ctx.checkbox.mark(task.done);
+        let lock = match (task.locked, ctx.lock_column) {
+            (true, _) => String::from(LOCK),
+            (false, true) => " ".repeat(LOCK.width()),
+            (false, false) => String::new(),
+        };
+        let prefix = format!("{}{}{} {}", gutter, indent, mark, lock);
+        let selected = ctx.selected == Some(index);
+        let theme = ctx.theme;
+        let marker = match task.priority {
+            Priority::Urgent => Some(("! ", theme.urgent)),
+            Priority::High => Some(("! ", theme.high)),
+            Priority::Normal | Priority::Low => None,
+        };
+        let lead = prefix.width() + marker.map_or(0, |(m, _)| m.width());
+
+        let msg = sanitize_line(&task.msg);
+        // Only the title is filtered on, not the badge and due date after it.
+        let matched = match (ctx.editing, ctx.query) {
+            (None, Some(query)) => find_lowercase(&msg, query).map(|range| (range, theme.matched)),
+            _ => None,
+        };
+        let mut title = match task.details {
+            Some(ref details) if details.len() > LARGE_DETAILS => {
+                format!("{} [{}]", msg, human_size(details.len()))
+            }
+            _ => msg,
+        };
+        let mut style = Style::default();
+        if let Some(due) = task.due {
+            title = format!("{}  due {}", title, due);
+            if !task.done && due == ctx.today {
+                style = theme.due_today;
+            } else if !task.done && due < ctx.today {
+                style = theme.overdue;
+            }
+        }
+
+        let (text, style, marker, cursor) = match ctx.editing {
+            Some((input, at)) if selected => {
+                let before = sanitize_line(&input[..at]);
+                let after = sanitize_line(&input[at..]);
+                let room = ctx.width.saturating_sub(lead + 1);
+                let before = visible_tail(&before, room);
+                let after = visible_head(&after, room - before.width());
+                let cursor = u16::try_from(lead + before.width()).unwrap_or(u16::MAX);
+                (
+                    format!("{}{}", before, after),
+                    Style::default(),
+                    marker,
+                    Some(cursor),
+                )
+            }
+            Some(_) => (
+                title,
+                theme.dimmed,
+                marker.map(|(m, _)| (m, theme.dimmed)),
+                None,
+            ),
+            None => (title, style, marker, None),
+        };
+        TaskView {
+            prefix,
+            marker,
+            text,
+            style,
+            matched,
+            cursor,
+        }
+    }
+
+    pub fn list_item(&self) -> ListItem<'static> {
+        let mut spans = vec![Span::styled(self.prefix.clone(), self.style)];
+        if let Some((marker, style)) = self.marker {
+            spans.push(Span::styled(marker, style));
+        }
+        match &self.matched {
+            Some((range, matched)) => {
+                spans.push(Span::styled(
+                    self.text[..range.start].to_string(),
+                    self.style,
+                ));
+                spans.push(Span::styled(
+                    self.text[range.clone()].to_string(),
+                    self.style.patch(*matched),
+                ));
+                spans.push(Span::styled(self.text[range.end..].to_string(), self.style));
+            }
+            None => spans.push(Span::styled(self.text.clone(), self.style)),
+        }
+        ListItem::new(Spans::from(spans))
+    }
+}
+
+/// Bytes of the first part of `text` whose lowercase is `query`.
+fn find_lowercase(text: &str, query: &str) -> Option<Range<usize>> {
+    if query.is_empty() {
+        return None;
+    }
+    for (start, _) in text.char_indices() {
+        let mut lower = String::new();
+        for (i, c) in text[start..].char_indices() {
+            lower.extend(c.to_lowercase());
+            if lower == query {
+                return Some(start..start + i + c.len_utf8());
+            }
+            if !query.starts_with(&lower) {
+                break;
+            }
+        }
+    }
+    None
+}
+
+/// Longest suffix of `text` fitting in `width` columns, so the end of an
+/// input being typed stays visible.
+fn visible_tail(text: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, c) in text.char_indices().rev() {
+        used += c.width().unwrap_or(0);
+        if used > width {
+            return &text[i + c.len_utf8()..];
+        }
+    }
+    text
+}
+
+/// Longest prefix of `text` fitting in `width` columns.
+fn visible_head(text: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, c) in text.char_indices() {
+        used += c.width().unwrap_or(0);
+        if used > width {
+            return &text[..i];
+        }
+    }
+    text
+}
+
+/// Formats a byte count as a short human readable size, e.g. `1.5 MiB`.
+pub(super) fn human_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(theme: &Theme, len: usize) -> RowContext<'_> {
+        RowContext::new(None, len, RowFormat::default(), theme, None, None, 80)
+    }
+
+    #[test]
+    fn lock_column_keeps_titles_aligned() {
+        let mut locked = Task::new(String::from("Locked"), None);
+        locked.locked = true;
+        let unlocked = Task::new(String::from("Free"), None);
+        let theme = Theme::default();
+        let mut ctx = ctx(&theme, 2);
+        ctx.lock_column = true;
+        let locked = TaskView::new(&locked, 0, &ctx);
+        let unlocked = TaskView::new(&unlocked, 1, &ctx);
+        assert!(locked.prefix.contains(LOCK));
+        assert_eq!(locked.prefix.width(), unlocked.prefix.width());
+
+        ctx.lock_column = false;
+        let free = TaskView::new(&Task::new(String::from("Free"), None), 1, &ctx);
+        assert_eq!(free.prefix.width() + LOCK.width(), locked.prefix.width());
+    }
+
+    #[test]
+    fn task_view_builds_the_prefix_marker_and_text() {
+        let mut task = Task::new(String::from("Ship\nit"), None);
+        task.done = true;
+        task.subtask = true;
+        task.priority = Priority::Urgent;
+        let theme = Theme::default();
+        let view = TaskView::new(&task, 0, &ctx(&theme, 1));
+        assert_eq!(view.prefix, "  ✓ ");
+        assert_eq!(view.marker.map(|(m, _)| m), Some("! "));
+        assert_eq!(view.text, "Ship it");
+        assert_eq!(view.cursor, None);
+
+        let mut task = Task::new(String::from("Log"), Some("x".repeat(LARGE_DETAILS + 1)));
+        task.due = NaiveDate::from_ymd_opt(2020, 1, 2);
+        let view = TaskView::new(&task, 0, &ctx(&theme, 1));
+        assert_eq!(view.marker, None);
+        assert_eq!(view.text, "Log [64.0 KiB]  due 2020-01-02");
+        assert_eq!(view.style, theme.overdue);
+    }
+
+    #[test]
+    fn task_view_numbers_rows() {
+        let task = Task::new(String::from("t"), None);
+        let format = |numbers| RowFormat {
+            numbers,
+            ..RowFormat::default()
+        };
+        let theme = Theme::default();
+        let ctx = RowContext::new(
+            Some(1),
+            12,
+            format(NumberMode::Relative),
+            &theme,
+            None,
+            None,
+            80,
+        );
+        assert_eq!(TaskView::new(&task, 1, &ctx).prefix, " 2   ");
+        assert_eq!(TaskView::new(&task, 4, &ctx).prefix, " 3   ");
+        let ctx = RowContext::new(
+            Some(1),
+            12,
+            format(NumberMode::Absolute),
+            &theme,
+            None,
+            None,
+            80,
+        );
+        assert_eq!(TaskView::new(&task, 4, &ctx).prefix, " 5   ");
+    }
+
+    #[test]
+    fn task_view_marks_the_match_and_places_the_cursor() {
+        let mut task = Task::new(String::from("Buy MILK"), None);
+        let theme = Theme::default();
+        let mut ctx = ctx(&theme, 1);
+        ctx.query = Some("milk");
+        let view = TaskView::new(&task, 0, &ctx);
+        assert_eq!(view.matched.map(|(range, _)| range), Some(4..8));
+
+        task.due = NaiveDate::from_ymd_opt(2020, 1, 2);
+        ctx.query = Some("due");
+        assert_eq!(TaskView::new(&task, 0, &ctx).matched, None);
+
+        ctx.selected = Some(0);
+        ctx.editing = Some(("Buy eggs", 3));
+        let view = TaskView::new(&task, 0, &ctx);
+        assert_eq!(view.text, "Buy eggs");
+        assert_eq!(view.matched, None);
+        assert_eq!(
+            view.cursor,
+            Some(u16::try_from(view.prefix.width()).unwrap() + 3)
+        );
+    }
+
+    #[test]
+    fn checkbox_marks_have_one_width() {
+        assert_eq!(Checkbox::Check.mark(true), "✓");
+        assert_eq!(Checkbox::Check.mark(false), " ");
+        assert_eq!(Checkbox::Brackets.mark(true), "[x]");
+        assert_eq!(Checkbox::Brackets.mark(false), "[ ]");
+    }
+
+    #[test]
+    fn find_lowercase_matches_ignoring_case() {
+        assert_eq!(find_lowercase("Hello World", "world"), Some(6..11));
+        assert_eq!(find_lowercase("ÉCOLE", "école"), Some(0..6));
+        assert_eq!(find_lowercase("abc", "abd"), None);
+        assert_eq!(find_lowercase("abc", ""), None);
+    }
+
+    #[test]
+    fn visible_tail_and_head_count_columns() {
+        assert_eq!(visible_tail("abcdef", 3), "def");
+        assert_eq!(visible_head("abcdef", 3), "abc");
+        assert_eq!(visible_tail("ab漢字", 3), "字");
+        assert_eq!(visible_head("漢字ab", 3), "漢");
+        assert_eq!(visible_tail("ab", 5), "ab");
+        assert_eq!(visible_head("ab", 0), "");
+    }
+
+    #[test]
+    fn human_size_picks_a_unit() {
+        assert_eq!(human_size(0), "0 B");
+        assert_eq!(human_size(1023), "1023 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
@@ -0,0 +1,379 @@
+//! The task list as a standalone widget, for embedding in other tui apps.
+//!
+//! ```no_run
+//! # use tui::{backend::Backend, Frame, widgets::{Block, Borders}};
+//! use todo_tui::{
+//!     task::Task,
+//!     ui::widget::{TaskListState, TaskListWidget},
+//! };
+//!
+//! fn draw<B: Backend>(f: &mut Frame<B>, tasks: &[Task], state: &mut TaskListState) {
+//!     let widget = TaskListWidget::new(tasks).block(Block::default().borders(Borders::ALL));
+//!     f.render_stateful_widget(widget, f.size(), state);
+//! }
+//! ```
+
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, List, ListState, StatefulWidget, Widget},
+};
+
+use super::view::{RowContext, TaskView};
+use crate::task::Task;
+
+pub use super::view::{Checkbox, NumberMode};
+
+/// Styles used to draw the task list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// Selected row.
+    pub highlight: Style,
+    /// Rows in the background while the selected one is edited inline.
+    pub dimmed: Style,
+    /// Selected row while it is flashed to draw the eye.
+    pub accent: Style,
+    /// Pending tasks due today.
+    pub due_today: Style,
+    /// Pending tasks past their due date.
+    pub overdue: Style,
+    /// Marker of high priority tasks.
+    pub high: Style,
+    /// Marker of urgent tasks.
+    pub urgent: Style,
+    /// Part of a title matching the filter.
+    pub matched: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            highlight: Style::default().fg(Color::Black).bg(Color::White),
+            dimmed: Style::default().add_modifier(Modifier::DIM),
+            accent: Style::default()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            due_today: Style::default().fg(Color::Yellow),
+            overdue: Style::default().fg(Color::Red),
+            high: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            urgent: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            matched: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+        }
+    }
+}
+
+/// How each row is laid out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RowFormat {
+    pub numbers: NumberMode,
+    pub checkbox: Checkbox,
+}
+
+/// Selection and scrolling of a [`TaskListWidget`], kept between frames.
+#[derive(Debug, Clone, Default)]
+pub struct TaskListState {
+    selected: Option<usize>,
+    offset: usize,
+    cursor: Option<(u16, u16)>,
+}
+
+impl TaskListState {
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: Option<usize>) {
+        self.selected = index;
+        if index.is_none() {
+            self.offset = 0;
+        }
+    }
+
+    /// Selects the row after the current one in a list of `len` rows,
+    /// wrapping to the top. An empty list leaves the state unchanged.
+    pub fn next(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
+        let i = match self.selected {
+            Some(i) if i + 1 < len => i + 1,
+            _ => 0,
+        };
+        self.select(Some(i));
+    }
+
+    /// Selects the row before the current one in a list of `len` rows,
+    /// wrapping to the bottom. An empty list leaves the state unchanged.
+    pub fn previous(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
+        let i = match self.selected {
+            Some(0) => len - 1,
+            Some(i) => i - 1,
+            None => 0,
+        };
+        self.select(Some(i));
+    }
+
+    /// Screen position of the inline editor's cursor, as of the last render.
+    pub fn cursor(&self) -> Option<(u16, u16)> {
+        self.cursor
+    }
+
+    /// Scrolls just enough for `row`, the selection's position among the
+    /// `len` displayed rows, to fit in `height` rows and returns the index
+    /// of the first visible row.
+    fn scroll_into_view(&mut self, row: Option<usize>, len: usize, height: usize) -> usize {
+        if let Some(i) = row {
+            if i < self.offset {
+                self.offset = i;
+            } else if height > 0 && i >= self.offset + height {
+                self.offset = i + 1 - height;
+            }
+        }
+        self.offset = self.offset.min(len.saturating_sub(1));
+        self.offset
+    }
+}
+
+/// Renders a slice of tasks as a scrollable, selectable list.
+pub struct TaskListWidget<'a> {
+    tasks: &'a [Task],
+    block: Option<Block<'a>>,
+    theme: Theme,
+    format: RowFormat,
+    editing: Option<(&'a str, usize)>,
+    query: Option<&'a str>,
+    flash: bool,
+    rows: Option<&'a [usize]>,
+}
+
+impl<'a> TaskListWidget<'a> {
+    pub fn new(tasks: &'a [Task]) -> Self {
+        TaskListWidget {
+            tasks,
+            block: None,
+            theme: Theme::default(),
+            format: RowFormat::default(),
+            editing: None,
+            query: None,
+            flash: false,
+            rows: None,
+        }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn format(mut self, format: RowFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Shows `input` in place of the selected task's title, with a cursor
+    /// at the given byte offset.
+    pub fn editing(mut self, input: Option<(&'a str, usize)>) -> Self {
+        self.editing = input;
+        self
+    }
+
+    /// Marks in each title the first match of `query`, given in lowercase.
+    pub fn query(mut self, query: Option<&'a str>) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Shows only the tasks at these indices, in this order. The state's
+    /// selection keeps referring to an index in the whole slice.
+    pub fn rows(mut self, rows: &'a [usize]) -> Self {
+        self.rows = Some(rows);
+        self
+    }
+
+    /// Draws the selected row with the theme's accent instead of the
+    /// highlight.
+    pub fn flash(mut self, flash: bool) -> Self {
+        self.flash = flash;
+        self
+    }
+}
+
+impl StatefulWidget for TaskListWidget<'_> {
+    type State = TaskListState;
+
+    fn render(mut self, area: Rect, buf: &mut Buffer, state: &mut TaskListState) {
+        let inner = match self.block.take() {
+            Some(block) => {
+                let inner = block.inner(area);
+                block.render(area, buf);
+                inner
+            }
+            None => area,
+        };
+
+        let all: Vec<usize>;
+        let rows = match self.rows {
+            Some(rows) => rows,
+            None => {
+                all = (0..self.tasks.len()).collect();
+                &all
+            }
+        };
+        let selected = state
+            .selected
+            .and_then(|s| rows.iter().position(|&i| i == s));
+
+        let height = usize::from(inner.height);
+        let offset = state.scroll_into_view(selected, rows.len(), height);
+        let mut ctx = RowContext::new(
+            selected,
+            rows.len(),
+            self.format,
+            &self.theme,
+            self.editing,
+            self.query,
+            usize::from(inner.width),
+        );
+        ctx.lock_column = rows.iter().any(|&i| self.tasks[i].locked);
+
+        state.cursor = None;
+        let mut items = Vec::with_capacity(height);
+        for (n, &i) in rows.iter().enumerate().skip(offset).take(height) {
+            let view = TaskView::new(&self.tasks[i], n, &ctx);
+            if let Some(cursor) = view.cursor {
+                state.cursor = Some((inner.x + cursor, inner.y + (n - offset) as u16));
+            }
+            items.push(view.list_item());
+        }
+
+        let highlight = if self.flash {
+            self.theme.accent
+        } else {
+            self.theme.highlight
+        };
+        let mut list_state = ListState::default();
+        list_state.select(selected.map(|n| n - offset));
+        StatefulWidget::render(
+            List::new(items).highlight_style(highlight),
+            inner,
+            buf,
+            &mut list_state,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Task;
+
+    fn state(selected: Option<usize>) -> TaskListState {
+        let mut state = TaskListState::default();
+        state.select(selected);
+        state
+    }
+
+    #[test]
+    fn next_and_previous_leave_an_empty_list_alone() {
+        for selected in [None, Some(0)] {
+            let mut state = state(selected);
+            state.next(0);
+            assert_eq!(state.selected(), selected);
+            state.previous(0);
+            assert_eq!(state.selected(), selected);
+        }
+    }
+
+    #[test]
+    fn next_and_previous_stay_on_a_single_row() {
+        let mut state = state(None);
+        state.next(1);
+        assert_eq!(state.selected(), Some(0));
+        state.next(1);
+        assert_eq!(state.selected(), Some(0));
+        state.previous(1);
+        assert_eq!(state.selected(), Some(0));
+        let mut state = self::state(None);
+        state.previous(1);
+        assert_eq!(state.selected(), Some(0));
+    }
+
+    #[test]
+    fn next_and_previous_wrap_around() {
+        let mut state = state(Some(1));
+        state.next(3);
+        assert_eq!(state.selected(), Some(2));
+        state.next(3);
+        assert_eq!(state.selected(), Some(0));
+        state.previous(3);
+        assert_eq!(state.selected(), Some(2));
+        state.previous(3);
+        assert_eq!(state.selected(), Some(1));
+    }
+
+    /// Rows drawn by a widget over `tasks` in a 20 columns, `height` rows area,
+    /// with trailing blanks trimmed.
+    fn render(tasks: &[Task], numbers: NumberMode, selected: usize, height: u16) -> Vec<String> {
+        let area = Rect::new(0, 0, 20, height);
+        let mut buf = Buffer::empty(area);
+        let mut state = TaskListState::default();
+        state.select(Some(selected));
+        let format = RowFormat {
+            numbers,
+            ..RowFormat::default()
+        };
+        TaskListWidget::new(tasks)
+            .format(format)
+            .render(area, &mut buf, &mut state);
+        (0..height)
+            .map(|y| {
+                let row: String = (0..area.width)
+                    .map(|x| buf.get(x, y).symbol.clone())
+                    .collect();
+                row.trim_end().to_string()
+            })
+            .collect()
+    }
+
+    fn named(n: usize) -> Vec<Task> {
+        (0..n).map(|i| Task::new(format!("t{}", i), None)).collect()
+    }
+
+    #[test]
+    fn renders_each_number_mode() {
+        let tasks = named(3);
+        assert_eq!(
+            render(&tasks, NumberMode::None, 1, 3),
+            ["  t0", "  t1", "  t2"]
+        );
+        assert_eq!(
+            render(&tasks, NumberMode::Absolute, 1, 3),
+            ["1   t0", "2   t1", "3   t2"]
+        );
+        assert_eq!(
+            render(&tasks, NumberMode::Relative, 1, 3),
+            ["1   t0", "2   t1", "1   t2"]
+        );
+    }
+
+    #[test]
+    fn gutter_widens_for_long_lists() {
+        let tasks = named(1200);
+        let rows = render(&tasks, NumberMode::Absolute, 1199, 2);
+        assert_eq!(rows, ["1199   t1198", "1200   t1199"]);
+        let rows = render(&tasks, NumberMode::Relative, 1199, 2);
+        assert_eq!(rows, ["   1   t1198", "1200   t1199"]);
+    }
+}